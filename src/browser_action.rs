@@ -0,0 +1,415 @@
+use std::time::Duration;
+
+use fantoccini::{Client, Locator, key::Key};
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+
+use crate::{fetch_error::FetchError, fetcher};
+
+const MAX_WAIT_MS: u64 = 10_000;
+const DEFAULT_WAIT_FOR_MS: u64 = 5_000;
+const DEFAULT_SCROLL_TIMES: u32 = 1;
+const MAX_SCROLL_TIMES: u32 = 50;
+const SCROLL_PAUSE_MS: u64 = 500;
+const SETTLE_AFTER_ACTION_MS: u64 = 300;
+
+const DEFAULT_MAX_SCROLLS: u32 = 30;
+const MAX_SCROLLS_LIMIT: u32 = 200;
+const DEFAULT_SCROLL_TIME_MS: u64 = 20_000;
+const MAX_SCROLL_TIME_MS: u64 = 60_000;
+const DEFAULT_SCROLL_PAUSE_MS: u64 = 1_000;
+// 连续多少轮 DOM 不再增长即认为已加载完毕
+const STABLE_ROUNDS: u32 = 2;
+
+const SCROLL_PROBE_SCRIPT: &str = r#"
+const [itemSelector, stopSelector] = arguments;
+window.scrollTo(0, document.documentElement.scrollHeight);
+return {
+    height: document.documentElement.scrollHeight,
+    items: itemSelector ? document.querySelectorAll(itemSelector).length : null,
+    stop: stopSelector ? document.querySelector(stopSelector) !== null : false
+};
+"#;
+
+// 先尝试常见 CMP 的“全部接受”按钮，再在识别出的同意弹窗内按按钮文字匹配通用的同意按钮；返回命中的框架名
+const CONSENT_SCRIPT: &str = r##"
+const known = [
+    ["onetrust", "#onetrust-accept-btn-handler"],
+    ["quantcast", ".qc-cmp2-summary-buttons button[mode='primary'], .qc-cmp-button"],
+    ["didomi", "#didomi-notice-agree-button"],
+    ["cookiebot", "#CybotCookiebotDialogBodyLevelButtonLevelOptinAllowAll, #CybotCookiebotDialogBodyButtonAccept"],
+    ["trustarc", "#truste-consent-button"]
+];
+for (const [name, selector] of known) {
+    const el = document.querySelector(selector);
+    if (el) {
+        el.click();
+        return name;
+    }
+}
+// 同意弹窗：id / class / aria-label 含 cookie、consent、gdpr，或文字提到 Cookie / 隐私的对话框
+const marker = /cookie|consent|gdpr/i;
+const mentions = /cookie|consent|gdpr|隐私/i;
+const containers = Array.from(document.querySelectorAll("body *")).filter(el =>
+    marker.test(el.id || "")
+    || marker.test(typeof el.className === "string" ? el.className : "")
+    || marker.test(el.getAttribute("aria-label") || "")
+    || (el.matches("[role='dialog'], [role='alertdialog'], [aria-modal='true'], dialog")
+        && mentions.test(el.innerText || ""))
+);
+if (containers.length === 0) {
+    return null;
+}
+const labels = [
+    "accept all", "accept all cookies", "allow all", "allow all cookies", "i agree", "agree",
+    "accept", "accept cookies", "got it", "全部接受", "接受全部", "接受所有", "同意", "接受",
+    "alle akzeptieren", "akzeptieren", "tout accepter", "accepter", "aceptar todo", "aceptar",
+    "accetta tutto", "accetta"
+];
+const candidates = Array.from(document.querySelectorAll(
+    "button, [role='button'], input[type='button'], input[type='submit']"
+)).filter(c => containers.some(container => container.contains(c)));
+for (const label of labels) {
+    const el = candidates.find(c => (c.innerText || c.value || "").trim().toLowerCase() === label);
+    if (el) {
+        el.click();
+        return "generic";
+    }
+}
+return null;
+"##;
+
+#[derive(Debug, Clone, Deserialize, schemars::JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BrowserAction {
+    #[schemars(description = "点击第一个匹配 CSS 选择器的元素")]
+    Click { selector: String },
+    #[schemars(description = "向匹配 CSS 选择器的输入框输入文本")]
+    Type { selector: String, text: String },
+    #[schemars(
+        description = "按键，例如 Enter、Tab、Escape、PageDown；未指定 selector 时发送给当前焦点元素"
+    )]
+    Press {
+        key: String,
+        #[serde(default)]
+        selector: Option<String>,
+    },
+    #[schemars(description = "滚动到页面底部 times 次（默认 1，上限 50），每次之间停顿 500ms")]
+    ScrollToBottom {
+        #[serde(default)]
+        times: Option<u32>,
+    },
+    #[schemars(description = "等待匹配 CSS 选择器的元素出现，timeout_ms 默认 5000，上限 10000")]
+    WaitForSelector {
+        selector: String,
+        #[serde(default)]
+        timeout_ms: Option<u64>,
+    },
+    #[schemars(description = "固定等待 ms 毫秒（上限 10000）")]
+    Wait { ms: u64 },
+}
+
+impl BrowserAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BrowserAction::Click { .. } => "click",
+            BrowserAction::Type { .. } => "type",
+            BrowserAction::Press { .. } => "press",
+            BrowserAction::ScrollToBottom { .. } => "scroll_to_bottom",
+            BrowserAction::WaitForSelector { .. } => "wait_for_selector",
+            BrowserAction::Wait { .. } => "wait",
+        }
+    }
+
+    // 动作最长可能占用的时间，用于放宽整体抓取超时
+    pub fn budget(&self) -> Duration {
+        let ms = match self {
+            BrowserAction::ScrollToBottom { times } => {
+                scroll_times(*times) as u64 * SCROLL_PAUSE_MS
+            }
+            BrowserAction::WaitForSelector { timeout_ms, .. } => {
+                timeout_ms.unwrap_or(DEFAULT_WAIT_FOR_MS).min(MAX_WAIT_MS)
+            }
+            BrowserAction::Wait { ms } => (*ms).min(MAX_WAIT_MS),
+            BrowserAction::Click { .. }
+            | BrowserAction::Type { .. }
+            | BrowserAction::Press { .. } => 0,
+        };
+        Duration::from_millis(ms + SETTLE_AFTER_ACTION_MS + 1000)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, schemars::JsonSchema)]
+pub struct InfiniteScroll {
+    #[serde(default)]
+    #[schemars(description = "最多滚动次数，默认 30，上限 200")]
+    pub max_scrolls: Option<u32>,
+    #[serde(default)]
+    #[schemars(description = "滚动总时长上限（毫秒），默认 20000，上限 60000")]
+    pub max_time_ms: Option<u64>,
+    #[serde(default)]
+    #[schemars(description = "页面高度达到该像素值后停止")]
+    pub max_height: Option<u64>,
+    #[serde(default)]
+    #[schemars(description = "列表项的 CSS 选择器，配合 max_items 使用")]
+    pub item_selector: Option<String>,
+    #[serde(default)]
+    #[schemars(description = "item_selector 匹配的元素数量达到该值后停止")]
+    pub max_items: Option<u64>,
+    #[serde(default)]
+    #[schemars(description = "出现匹配该 CSS 选择器的元素（如“没有更多了”）时停止")]
+    pub stop_selector: Option<String>,
+    #[serde(default)]
+    #[schemars(description = "每次滚动后等待新内容加载的时间（毫秒），默认 1000")]
+    pub pause_ms: Option<u64>,
+}
+
+impl InfiniteScroll {
+    pub fn budget(&self) -> Duration {
+        Duration::from_millis(self.max_time_ms()) + Duration::from_secs(5)
+    }
+
+    fn max_time_ms(&self) -> u64 {
+        self.max_time_ms
+            .unwrap_or(DEFAULT_SCROLL_TIME_MS)
+            .min(MAX_SCROLL_TIME_MS)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrollStopReason {
+    DomStable,
+    MaxScrolls,
+    MaxTime,
+    MaxHeight,
+    MaxItems,
+    StopSelector,
+}
+
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct ScrollOutcome {
+    pub scrolls: u32,
+    pub height: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<u64>,
+    pub stop_reason: ScrollStopReason,
+}
+
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct ActionOutcome {
+    pub index: usize,
+    pub action: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// 在同一页面上依次执行动作；单个动作失败时记录错误并继续执行后续动作
+pub async fn run_actions(client: &Client, actions: &[BrowserAction]) -> Vec<ActionOutcome> {
+    let mut outcomes = Vec::with_capacity(actions.len());
+    for (index, action) in actions.iter().enumerate() {
+        let result = run_action(client, action).await;
+        if let Err(e) = &result {
+            tracing::debug!("browser action #{index} {} failed: {e}", action.name());
+        }
+        outcomes.push(ActionOutcome {
+            index,
+            action: action.name().to_string(),
+            ok: result.is_ok(),
+            error: result.err().map(|e| e.to_string()),
+        });
+    }
+    outcomes
+}
+
+async fn run_action(client: &Client, action: &BrowserAction) -> Result<(), FetchError> {
+    match action {
+        BrowserAction::Click { selector } => {
+            client
+                .find(Locator::Css(selector))
+                .await
+                .map_err(|e| FetchError::from_cmd(&format!("find {selector} failed"), &e))?
+                .click()
+                .await
+                .map_err(|e| FetchError::from_cmd(&format!("click {selector} failed"), &e))?;
+            settle().await;
+        }
+        BrowserAction::Type { selector, text } => {
+            client
+                .find(Locator::Css(selector))
+                .await
+                .map_err(|e| FetchError::from_cmd(&format!("find {selector} failed"), &e))?
+                .send_keys(text)
+                .await
+                .map_err(|e| FetchError::from_cmd(&format!("type into {selector} failed"), &e))?;
+        }
+        BrowserAction::Press { key, selector } => {
+            let key = parse_key(key)
+                .ok_or_else(|| FetchError::browser(format!("unsupported key: {key}")))?;
+            let element = match selector {
+                Some(selector) => client
+                    .find(Locator::Css(selector))
+                    .await
+                    .map_err(|e| FetchError::from_cmd(&format!("find {selector} failed"), &e))?,
+                None => client
+                    .active_element()
+                    .await
+                    .map_err(|e| FetchError::from_cmd("find active element failed", &e))?,
+            };
+            element
+                .send_keys(&key.to_string())
+                .await
+                .map_err(|e| FetchError::from_cmd("press key failed", &e))?;
+            settle().await;
+        }
+        BrowserAction::ScrollToBottom { times } => {
+            for _ in 0..scroll_times(*times) {
+                client
+                    .execute(
+                        "window.scrollTo(0, document.documentElement.scrollHeight)",
+                        Vec::new(),
+                    )
+                    .await
+                    .map_err(|e| FetchError::from_cmd("scroll failed", &e))?;
+                tokio::time::sleep(Duration::from_millis(SCROLL_PAUSE_MS)).await;
+            }
+        }
+        BrowserAction::WaitForSelector {
+            selector,
+            timeout_ms,
+        } => {
+            let timeout = timeout_ms.unwrap_or(DEFAULT_WAIT_FOR_MS).min(MAX_WAIT_MS);
+            client
+                .wait()
+                .at_most(Duration::from_millis(timeout))
+                .for_element(Locator::Css(selector))
+                .await
+                .map_err(|e| FetchError::from_cmd(&format!("wait for {selector} failed"), &e))?;
+        }
+        BrowserAction::Wait { ms } => {
+            tokio::time::sleep(Duration::from_millis((*ms).min(MAX_WAIT_MS))).await;
+        }
+    }
+    Ok(())
+}
+
+// 点击 Cookie 同意弹窗的接受按钮，未找到弹窗时返回 None
+pub async fn dismiss_consent(client: &Client) -> Option<String> {
+    let dismissed = match client.execute(CONSENT_SCRIPT, Vec::new()).await {
+        Ok(value) => value.as_str().map(|name| name.to_string()),
+        Err(e) => {
+            tracing::debug!("consent dismissal script failed: {e}");
+            None
+        }
+    };
+    if dismissed.is_some() {
+        settle().await;
+    }
+    dismissed
+}
+
+// 反复滚动到底部，直到元素数量不再增长或触及任一上限
+pub async fn infinite_scroll(
+    client: &Client,
+    options: &InfiniteScroll,
+) -> Result<ScrollOutcome, FetchError> {
+    let max_scrolls = options
+        .max_scrolls
+        .unwrap_or(DEFAULT_MAX_SCROLLS)
+        .clamp(1, MAX_SCROLLS_LIMIT);
+    let deadline = tokio::time::Instant::now() + Duration::from_millis(options.max_time_ms());
+    let pause = Duration::from_millis(
+        options
+            .pause_ms
+            .unwrap_or(DEFAULT_SCROLL_PAUSE_MS)
+            .min(MAX_WAIT_MS),
+    );
+    let args = vec![
+        serde_json::json!(options.item_selector),
+        serde_json::json!(options.stop_selector),
+    ];
+
+    let mut last_count = fetcher::get_dom_node_count(client).await?;
+    let mut stable_rounds = 0;
+    let mut scrolls = 0;
+    loop {
+        let probe = client
+            .execute(SCROLL_PROBE_SCRIPT, args.clone())
+            .await
+            .map_err(|e| FetchError::from_cmd("scroll failed", &e))?;
+        scrolls += 1;
+        tokio::time::sleep(pause).await;
+
+        let height = probe["height"].as_u64().unwrap_or_default();
+        let items = probe["items"].as_u64();
+        let count = fetcher::get_dom_node_count(client).await?;
+        if count > last_count {
+            stable_rounds = 0;
+        } else {
+            stable_rounds += 1;
+        }
+        last_count = count;
+
+        let stop_reason = if probe["stop"].as_bool().unwrap_or(false) {
+            Some(ScrollStopReason::StopSelector)
+        } else if options
+            .max_items
+            .is_some_and(|max| items.is_some_and(|items| items >= max))
+        {
+            Some(ScrollStopReason::MaxItems)
+        } else if options.max_height.is_some_and(|max| height >= max) {
+            Some(ScrollStopReason::MaxHeight)
+        } else if stable_rounds >= STABLE_ROUNDS {
+            Some(ScrollStopReason::DomStable)
+        } else if scrolls >= max_scrolls {
+            Some(ScrollStopReason::MaxScrolls)
+        } else if tokio::time::Instant::now() >= deadline {
+            Some(ScrollStopReason::MaxTime)
+        } else {
+            None
+        };
+
+        if let Some(stop_reason) = stop_reason {
+            return Ok(ScrollOutcome {
+                scrolls,
+                height,
+                items,
+                stop_reason,
+            });
+        }
+    }
+}
+
+fn scroll_times(times: Option<u32>) -> u32 {
+    times
+        .unwrap_or(DEFAULT_SCROLL_TIMES)
+        .clamp(1, MAX_SCROLL_TIMES)
+}
+
+// 点击、按键后页面可能发起请求或切换视图，稍作等待再执行下一步
+async fn settle() {
+    tokio::time::sleep(Duration::from_millis(SETTLE_AFTER_ACTION_MS)).await;
+}
+
+fn parse_key(name: &str) -> Option<Key> {
+    let key = match name.trim().to_ascii_lowercase().as_str() {
+        "enter" => Key::Enter,
+        "return" => Key::Return,
+        "tab" => Key::Tab,
+        "escape" | "esc" => Key::Escape,
+        "space" => Key::Space,
+        "backspace" => Key::Backspace,
+        "delete" => Key::Delete,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "arrowup" | "up" => Key::Up,
+        "arrowdown" | "down" => Key::Down,
+        "arrowleft" | "left" => Key::Left,
+        "arrowright" | "right" => Key::Right,
+        _ => return None,
+    };
+    Some(key)
+}
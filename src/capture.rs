@@ -0,0 +1,155 @@
+use fantoccini::Client;
+use serde_json::{Value, json};
+
+use crate::{dom_filter::HIDDEN_MARKER_ATTR, fetch_error::FetchError};
+
+// 按计算样式标记不可见元素（含开放 shadow root 内的元素），返回标记数量；
+// 被裁剪为 1px 以内的 sr-only 写法同样视为不可见
+const TAG_HIDDEN_SCRIPT: &str = r##"
+const marker = arguments[0];
+let count = 0;
+function visit(root) {
+    for (const el of root.querySelectorAll("*")) {
+        if (el.shadowRoot) {
+            visit(el.shadowRoot);
+        }
+        const tag = el.tagName.toLowerCase();
+        // hidden="until-found" 的内容可被页内搜索展开，不标记
+        if (tag === "head" || tag === "script" || tag === "style" || el.closest("head")
+            || el.closest("[hidden='until-found']")) {
+            continue;
+        }
+        const style = getComputedStyle(el);
+        const clipped = style.overflow === "hidden"
+            && el.offsetWidth <= 1 && el.offsetHeight <= 1
+            && style.position === "absolute";
+        if (style.display === "none" || style.visibility === "hidden" || clipped) {
+            el.setAttribute(marker, "true");
+            count++;
+        }
+    }
+}
+visit(document);
+return count;
+"##;
+
+// 序列化当前页面：开放的 shadow root 按 slot 分配结果展开到宿主元素内，
+// 可选地把同源 iframe 的文档内联为 <div data-iframe-src="...">
+const CAPTURE_SCRIPT: &str = r##"
+const inlineIframes = arguments[0];
+const VOID_TAGS = new Set([
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta",
+    "param", "source", "track", "wbr"
+]);
+const RAW_TEXT_TAGS = new Set(["script", "style", "textarea"]);
+
+function escapeText(text) {
+    return text.replace(/&/g, "&amp;").replace(/</g, "&lt;").replace(/>/g, "&gt;");
+}
+
+function escapeAttr(value) {
+    return escapeText(value).replace(/"/g, "&quot;");
+}
+
+function serializeChildren(parent) {
+    let out = "";
+    for (const child of parent.childNodes) {
+        out += serializeNode(child);
+    }
+    return out;
+}
+
+function serializeNode(node) {
+    switch (node.nodeType) {
+        case Node.TEXT_NODE: {
+            const parent = node.parentNode;
+            const parentTag = parent && parent.tagName ? parent.tagName.toLowerCase() : "";
+            return RAW_TEXT_TAGS.has(parentTag) ? node.nodeValue : escapeText(node.nodeValue);
+        }
+        case Node.ELEMENT_NODE:
+            return serializeElement(node);
+        case Node.DOCUMENT_FRAGMENT_NODE:
+            return serializeChildren(node);
+        default:
+            return "";
+    }
+}
+
+function serializeElement(el) {
+    const tag = el.tagName.toLowerCase();
+
+    if (tag === "slot") {
+        const assigned = el.assignedNodes({ flatten: true });
+        if (assigned.length > 0) {
+            return assigned.map(serializeNode).join("");
+        }
+        return serializeChildren(el);
+    }
+
+    if (tag === "iframe" && inlineIframes) {
+        try {
+            const doc = el.contentDocument;
+            if (doc && doc.body) {
+                const src = el.getAttribute("src") || "";
+                return '<div data-iframe-src="' + escapeAttr(src) + '">'
+                    + serializeChildren(doc.body) + "</div>";
+            }
+        } catch (e) {
+            // 跨域 iframe 无法访问，保留原标签
+        }
+    }
+
+    let out = "<" + tag;
+    for (const attr of el.attributes) {
+        out += " " + attr.name + '="' + escapeAttr(attr.value) + '"';
+    }
+    out += ">";
+    if (VOID_TAGS.has(tag)) {
+        return out;
+    }
+
+    if (el.shadowRoot) {
+        out += serializeChildren(el.shadowRoot);
+    } else if (tag !== "template") {
+        out += serializeChildren(el);
+    }
+    return out + "</" + tag + ">";
+}
+
+return "<!DOCTYPE html>" + serializeElement(document.documentElement);
+"##;
+
+// 读取渲染后的 HTML；脚本执行失败时退回 WebDriver 的页面源码
+pub async fn capture_html(client: &Client, inline_iframes: bool) -> Result<String, FetchError> {
+    match client
+        .execute(CAPTURE_SCRIPT, vec![json!(inline_iframes)])
+        .await
+    {
+        Ok(Value::String(html)) => Ok(html),
+        Ok(_) => {
+            tracing::debug!("capture script returned non-string, fall back to page source");
+            read_source(client).await
+        }
+        Err(e) => {
+            tracing::debug!("capture script failed, fall back to page source: {e}");
+            read_source(client).await
+        }
+    }
+}
+
+pub async fn tag_hidden_elements(client: &Client) {
+    match client
+        .execute(TAG_HIDDEN_SCRIPT, vec![json!(HIDDEN_MARKER_ATTR)])
+        .await
+    {
+        Ok(count) => tracing::debug!("tagged {count} hidden elements"),
+        Err(e) => tracing::debug!("tag hidden elements failed: {e}"),
+    }
+}
+
+async fn read_source(client: &Client) -> Result<String, FetchError> {
+    client
+        .source()
+        .await
+        .map_err(|e| FetchError::from_cmd("read html failed", &e))
+}
@@ -0,0 +1,166 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentKind {
+    Html,
+    Xml,
+    Json,
+    PlainText,
+    Markdown,
+    Pdf,
+    Binary,
+}
+
+// 返回小写的 MIME 主体部分（去掉 charset 等参数）
+pub fn mime_essence(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+pub fn charset_param(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        if key.trim().eq_ignore_ascii_case("charset") {
+            Some(value.trim().trim_matches(['"', '\'']).to_ascii_lowercase())
+        } else {
+            None
+        }
+    })
+}
+
+pub fn from_header(content_type: &str) -> Option<ContentKind> {
+    let essence = mime_essence(content_type);
+    let kind = match essence.as_str() {
+        "" | "application/octet-stream" | "binary/octet-stream" => return None,
+        "text/html" | "application/xhtml+xml" => ContentKind::Html,
+        "text/markdown" | "text/x-markdown" => ContentKind::Markdown,
+        "text/xml" | "application/xml" => ContentKind::Xml,
+        "application/json" | "text/json" => ContentKind::Json,
+        "application/pdf" | "application/x-pdf" => ContentKind::Pdf,
+        "text/plain" => ContentKind::PlainText,
+        other if other.ends_with("+xml") => ContentKind::Xml,
+        other if other.ends_with("+json") => ContentKind::Json,
+        other if other.starts_with("text/") => ContentKind::PlainText,
+        "application/javascript" | "application/ecmascript" | "application/x-sh" => {
+            ContentKind::PlainText
+        }
+        _ => ContentKind::Binary,
+    };
+    Some(kind)
+}
+
+pub fn sniff(prefix: &[u8]) -> ContentKind {
+    const BINARY_MAGIC: [&[u8]; 9] = [
+        b"\x89PNG",
+        b"\xFF\xD8\xFF",
+        b"GIF8",
+        b"PK\x03\x04",
+        b"\x1F\x8B",
+        b"RIFF",
+        b"OggS",
+        b"ID3",
+        b"7z\xBC\xAF",
+    ];
+
+    if prefix.starts_with(b"%PDF-") {
+        return ContentKind::Pdf;
+    }
+    if BINARY_MAGIC.iter().any(|magic| prefix.starts_with(magic)) {
+        return ContentKind::Binary;
+    }
+
+    let probe = &prefix[..prefix.len().min(1024)];
+    if probe.contains(&0) {
+        return ContentKind::Binary;
+    }
+
+    let lower = probe_lowercase(probe);
+    if looks_like_html(prefix) {
+        ContentKind::Html
+    } else if lower.starts_with("<?xml") || lower.starts_with("<rss") || lower.starts_with("<feed")
+    {
+        ContentKind::Xml
+    } else if lower.starts_with('<') {
+        ContentKind::Html
+    } else if lower.starts_with('{') || lower.starts_with('[') {
+        ContentKind::Json
+    } else {
+        ContentKind::PlainText
+    }
+}
+
+fn probe_lowercase(prefix: &[u8]) -> String {
+    let text = String::from_utf8_lossy(&prefix[..prefix.len().min(1024)]);
+    text.trim_start_matches('\u{feff}')
+        .trim_start()
+        .chars()
+        .take(256)
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+fn looks_like_html(prefix: &[u8]) -> bool {
+    let lower = probe_lowercase(prefix);
+    lower.starts_with("<!doctype html") || lower.starts_with("<html") || lower.contains("<body")
+}
+
+pub fn detect(content_type: Option<&str>, prefix: &[u8]) -> ContentKind {
+    match content_type.and_then(from_header) {
+        // 服务器经常把 HTML 错标为 text/plain，此时以内容为准
+        Some(ContentKind::PlainText) if looks_like_html(prefix) => ContentKind::Html,
+        Some(kind) => kind,
+        None => sniff(prefix),
+    }
+}
+
+// 编码优先取 Content-Type 的 charset；没有时 HTML 读取 <meta> 声明、XML 读取 <?xml encoding?> 声明，
+// JSON、纯文本等其他类型按 UTF-8 解码（encoding_rs 会先识别 BOM）
+pub fn decode_text(bytes: &[u8], content_type: Option<&str>) -> String {
+    let label =
+        content_type
+            .and_then(charset_param)
+            .or_else(|| match detect(content_type, bytes) {
+                ContentKind::Html => sniff_meta_charset(bytes),
+                ContentKind::Xml => sniff_xml_encoding(bytes),
+                _ => None,
+            });
+
+    let encoding = label
+        .as_deref()
+        .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+
+    let (text, _, _) = encoding.decode(bytes);
+    text.into_owned()
+}
+
+// 只在 <meta> 标签内查找 charset=，覆盖 <meta charset> 与 http-equiv 两种写法
+fn sniff_meta_charset(bytes: &[u8]) -> Option<String> {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(2048)]).to_ascii_lowercase();
+    head.match_indices("<meta").find_map(|(pos, _)| {
+        let tag = &head[pos..];
+        let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+        let value = tag.split_once("charset=")?.1;
+        label_value(value)
+    })
+}
+
+// XML 声明中的 encoding，例如 <?xml version="1.0" encoding="ISO-8859-1"?>
+fn sniff_xml_encoding(bytes: &[u8]) -> Option<String> {
+    let probe = probe_lowercase(bytes);
+    let declaration = probe.strip_prefix("<?xml")?;
+    let declaration = &declaration[..declaration.find("?>")?];
+    let value = declaration.split_once("encoding")?.1.trim_start();
+    label_value(value.strip_prefix('=')?.trim_start())
+}
+
+fn label_value(raw: &str) -> Option<String> {
+    let value: String = raw
+        .trim_start_matches(['"', '\''])
+        .chars()
+        .take_while(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_'))
+        .collect();
+    if value.is_empty() { None } else { Some(value) }
+}
@@ -1,37 +1,171 @@
-use crate::parser::HtmlNode;
+use std::collections::HashMap;
 
-#[derive(Debug, Clone)]
-enum ListType {
-    Ordered(usize),
-    Unordered,
+use rmcp::schemars;
+use serde::Deserialize;
+
+use crate::{
+    footnote::Footnotes,
+    math::{self, MathNode},
+    parser::{self, Attr, HtmlNode, attr},
+    text,
+    toc::{self, AnchorSet, Heading},
+};
+
+// 代码块的行号栏：Pygments、Rouge、highlight.js 行号插件、Prism、CodeMirror、GitHub 等使用的 class
+const GUTTER_CLASSES: [&str; 10] = [
+    "linenos",
+    "linenodiv",
+    "lineno",
+    "line-number",
+    "line-numbers-rows",
+    "hljs-ln-numbers",
+    "rouge-gutter",
+    "code-gutter",
+    "cm-gutters",
+    "blob-num",
+];
+
+// 代码块上的复制按钮
+const COPY_MARKERS: [&str; 2] = ["copy", "clipboard"];
+
+// 语法高亮器包裹代码块的容器，行号栏与复制按钮只在其中（及 <pre> 内）移除
+const CODE_CONTAINER_CLASSES: [&str; 11] = [
+    "highlighttable",
+    "codehilite",
+    "sourcecode",
+    "chroma",
+    "code-block",
+    "code-toolbar",
+    "highlighter-rouge",
+    "rouge-table",
+    "blob-wrapper",
+    "snippet-clipboard-content",
+    "cm-editor",
+];
+// 含义过于宽泛的类名（如正文中的高亮提示框），只有内部包含 <pre> 时才视为代码容器
+const GENERIC_CODE_CONTAINER_CLASSES: [&str; 1] = ["highlight"];
+
+// 表示“无语言”的标记，不写入围栏信息串
+const PLAIN_LANGUAGES: [&str; 5] = ["none", "nohighlight", "plain", "plaintext", "text"];
+
+// 文本中 Markdown 特殊字符的转义程度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EscapeMode {
+    // 只转义在当前位置确实会被解析为标记的字符（默认）
+    #[default]
+    Minimal,
+    // 转义所有可能具有 Markdown 含义的字符
+    Strict,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownOptions {
+    pub escape: EscapeMode,
+    // 在标题后追加 {#id} 锚点
+    pub heading_anchors: bool,
+}
+
+// Markdown 及转换过程中遇到的标题
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownDocument {
+    pub markdown: String,
+    pub headings: Vec<Heading>,
+}
+
+// 有序列表的编号样式，对应 <ol type>
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ListStyle {
+    Bullet,
+    Decimal,
+    LowerAlpha,
+    UpperAlpha,
+    LowerRoman,
+    UpperRoman,
+}
+
+// 列表项内可以直接接在文字后面、无需空行的块（列表、代码围栏、引用）
+const INTERRUPTING_PREFIXES: [&str; 4] = ["- ", "1. ", "```", "> "];
+
+// 列表项内按块处理、与前后文字分行的子元素
+const LIST_ITEM_BLOCK_TAGS: [&str; 17] = [
+    "p",
+    "div",
+    "ul",
+    "ol",
+    "pre",
+    "blockquote",
+    "table",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "section",
+    "article",
+    "figure",
+];
+
 #[derive(Debug, Clone)]
 struct ConvertContext {
-    indent_level: usize,
-    list_stack: Vec<ListType>,
-    in_code_block: bool,
     in_table: bool,
+    // 外层容器（如 <div class="highlight-source-rust">）上标注的代码语言
+    code_language: Option<String>,
+    // 位于 <pre> 或语法高亮容器内
+    in_code_block: bool,
+    escape: EscapeMode,
+    // 当前节点的输出位于行首，此时开头的块级标记需要转义
+    at_line_start: bool,
+    footnotes: Footnotes,
+    // 脚注标签 → 转换后的脚注正文，输出在文末
+    footnote_definitions: HashMap<String, String>,
+    heading_anchors: bool,
+    anchors: AnchorSet,
+    headings: Vec<Heading>,
 }
 
 impl ConvertContext {
-    fn new() -> Self {
+    fn new(options: &MarkdownOptions, footnotes: Footnotes) -> Self {
         Self {
-            indent_level: 0,
-            list_stack: Vec::new(),
-            in_code_block: false,
             in_table: false,
+            code_language: None,
+            in_code_block: false,
+            escape: options.escape,
+            at_line_start: true,
+            footnotes,
+            footnote_definitions: HashMap::new(),
+            heading_anchors: options.heading_anchors,
+            anchors: AnchorSet::default(),
+            headings: Vec::new(),
         }
     }
 }
 
 pub fn convert_to_markdown(nodes: &[HtmlNode]) -> String {
-    let mut ctx = ConvertContext::new();
-    let mut output = String::new();
-    for node in nodes {
-        output.push_str(&convert_node(node, &mut ctx));
+    convert_to_markdown_with(nodes, &MarkdownOptions::default())
+}
+
+pub fn convert_to_markdown_with(nodes: &[HtmlNode], options: &MarkdownOptions) -> String {
+    convert_to_markdown_document(nodes, options).markdown
+}
+
+pub fn convert_to_markdown_document(
+    nodes: &[HtmlNode],
+    options: &MarkdownOptions,
+) -> MarkdownDocument {
+    let mut ctx = ConvertContext::new(options, Footnotes::collect(nodes));
+    let mut output = convert_children(nodes, &mut ctx).trim().to_string();
+    let definitions = ctx.footnotes.render_definitions(&ctx.footnote_definitions);
+    if !definitions.is_empty() {
+        output.push_str("\n\n");
+        output.push_str(definitions.trim_end());
+    }
+    MarkdownDocument {
+        markdown: output,
+        headings: ctx.headings,
     }
-    output.trim().to_string()
 }
 
 fn convert_node(node: &HtmlNode, ctx: &mut ConvertContext) -> String {
@@ -39,95 +173,556 @@ fn convert_node(node: &HtmlNode, ctx: &mut ConvertContext) -> String {
         HtmlNode::Text(text) => process_text(text, ctx),
         HtmlNode::Element {
             tag,
-            attrs: _,
+            attrs,
             children,
-        } => match tag.as_str() {
-            "h1" => format!("# {}\n\n", convert_children(children, ctx).trim()),
-            "h2" => format!("## {}\n\n", convert_children(children, ctx).trim()),
-            "h3" => format!("### {}\n\n", convert_children(children, ctx).trim()),
-            "h4" => format!("#### {}\n\n", convert_children(children, ctx).trim()),
-            "h5" => format!("##### {}\n\n", convert_children(children, ctx).trim()),
-            "h6" => format!("###### {}\n\n", convert_children(children, ctx).trim()),
-            "p" => {
-                let content = convert_children(children, ctx).trim().to_string();
-                if content.is_empty() {
-                    String::new()
-                } else {
-                    format!("{}\n\n", content)
-                }
+        } if !ctx.in_code_block && is_code_container(tag, attrs, children) => {
+            ctx.in_code_block = true;
+            let content = convert_node(node, ctx);
+            ctx.in_code_block = false;
+            content
+        }
+        HtmlNode::Element { tag, attrs, .. }
+            if (ctx.in_code_block && (is_gutter(attrs) || is_copy_button(tag, attrs)))
+                || ctx.footnotes.is_backlink(tag, attrs) =>
+        {
+            String::new()
+        }
+        // 脚注正文移到文末，原位置不再输出
+        HtmlNode::Element {
+            attrs, children, ..
+        } if ctx.footnotes.definition_label(attrs).is_some() => {
+            let label = ctx
+                .footnotes
+                .definition_label(attrs)
+                .unwrap_or_default()
+                .to_string();
+            let content = convert_children(children, ctx).trim().to_string();
+            ctx.footnote_definitions.entry(label).or_insert(content);
+            String::new()
+        }
+        HtmlNode::Element {
+            tag,
+            attrs,
+            children,
+        } if matches!(tag.as_str(), "div" | "figure")
+            && container_language(attrs, children).is_some() =>
+        {
+            let outer = ctx
+                .code_language
+                .replace(container_language(attrs, children).unwrap_or_default());
+            let content = convert_element(tag, attrs, children, ctx);
+            ctx.code_language = outer;
+            content
+        }
+        HtmlNode::Element {
+            tag,
+            attrs,
+            children,
+        } => convert_element(tag, attrs, children, ctx),
+    }
+}
+
+fn convert_element(
+    tag: &str,
+    attrs: &[Attr],
+    children: &[HtmlNode],
+    ctx: &mut ConvertContext,
+) -> String {
+    // 公式优先于按标签转换，例如 KaTeX 的 span 和 MathJax 的 script
+    match math::classify(tag, attrs, children) {
+        Some(MathNode::Formula(formula)) => return math::to_markdown(&formula, ctx.in_table),
+        Some(MathNode::Rendered) => return String::new(),
+        None => {}
+    }
+    match tag {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => convert_heading(tag, attrs, children, ctx),
+        "p" => {
+            let content = convert_children(children, ctx).trim().to_string();
+            if content.is_empty() {
+                String::new()
+            } else {
+                format!("{}\n\n", content)
             }
-            "br" => "  \n".to_string(),
-            "hr" => "---\n\n".to_string(),
-            "strong" | "b" => format!("**{}**", convert_children(children, ctx)),
-            "em" | "i" => format!("*{}*", convert_children(children, ctx)),
-            "del" | "s" | "strike" => format!("~~{}~~", convert_children(children, ctx)),
-            "code" => {
-                if ctx.in_code_block {
-                    convert_children(children, ctx)
-                } else {
-                    format!("`{}`", convert_children(children, ctx))
-                }
+        }
+        "br" => "  \n".to_string(),
+        "hr" => "---\n\n".to_string(),
+        "strong" | "b" => format!("**{}**", convert_children(children, ctx)),
+        "em" | "i" => format!("*{}*", convert_children(children, ctx)),
+        "del" | "s" | "strike" => format!("~~{}~~", convert_children(children, ctx)),
+        "cite" => wrap_inline("*", &convert_children(children, ctx)),
+        "kbd" => code_span(children, ctx),
+        // 脚注引用输出为 [^1]，不保留外层 <sup>
+        "sup" if is_footnote_reference_group(children, ctx) => {
+            convert_children(children, ctx).trim().to_string()
+        }
+        "sup" | "sub" | "mark" => {
+            let content = convert_children(children, ctx);
+            if content.trim().is_empty() {
+                String::new()
+            } else {
+                format!("<{tag}>{}</{tag}>", content.trim())
             }
-            "pre" => {
-                ctx.in_code_block = true;
-                let content = convert_children(children, ctx);
-                ctx.in_code_block = false;
-                format!("```\n{}\n```\n\n", content.trim_end())
-            }
-            "a" => convert_children(children, ctx),
-            "img" => String::new(),
-            "ul" => convert_list(children, ctx, false),
-            "ol" => convert_list(children, ctx, true),
-            "li" => convert_list_item(children, ctx),
-            "blockquote" => {
-                let content = convert_children(children, ctx);
-                let lines: Vec<String> = content.lines().map(|line| format!("> {}", line)).collect();
-                format!("{}\n\n", lines.join("\n"))
-            }
-            "table" => convert_table(children, ctx),
-            "tr" | "td" | "th" => convert_children(children, ctx),
-            "div" | "section" | "article" | "span" => convert_children(children, ctx),
-            "script" | "style" | "head" | "noscript" => String::new(),
-            _ => convert_children(children, ctx),
+        }
+        "q" => format!("\"{}\"", convert_children(children, ctx)),
+        "abbr" | "time" => {
+            let name = if tag == "abbr" { "title" } else { "datetime" };
+            let annotation = attr(attrs, name)
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(|value| escape_text(value, ctx.escape, false));
+            with_annotation(convert_children(children, ctx), annotation)
+        }
+        "code" => code_span(children, ctx),
+        "pre" => convert_code_block(attrs, children, ctx),
+        "a" => match ctx.footnotes.reference_label(attrs) {
+            Some(label) => format!("[^{label}]"),
+            None => convert_children(children, ctx),
         },
+        "img" => String::new(),
+        "ul" => convert_list(attrs, children, ctx, false),
+        "ol" => convert_list(attrs, children, ctx, true),
+        "li" => convert_list_item("- ", children, ctx),
+        "blockquote" => {
+            let content = convert_children(children, ctx);
+            let lines: Vec<String> = content.lines().map(|line| format!("> {}", line)).collect();
+            format!("{}\n\n", lines.join("\n"))
+        }
+        // Pygments 等用表格把行号栏与代码并排，行号栏去掉后直接输出代码块
+        "table" if is_gutter_table(children) => convert_children(children, ctx),
+        "table" => convert_table(children, ctx),
+        "tr" | "td" | "th" => convert_children(children, ctx),
+        "dl" => convert_definition_list(children, ctx),
+        "figure" => convert_figure(children, ctx),
+        "details" => convert_details(children, ctx),
+        "address" => {
+            let content = convert_children(children, ctx).trim().to_string();
+            if content.is_empty() {
+                String::new()
+            } else {
+                format!("{}\n\n", content)
+            }
+        }
+        "div" | "section" | "article" | "span" => convert_children(children, ctx),
+        "script" | "style" | "head" | "noscript" => String::new(),
+        _ => convert_children(children, ctx),
     }
 }
 
+// 记录标题供目录使用；开启 heading_anchors 时以 {#id} 标注锚点
+fn convert_heading(
+    tag: &str,
+    attrs: &[Attr],
+    children: &[HtmlNode],
+    ctx: &mut ConvertContext,
+) -> String {
+    let level = tag[1..].parse::<u8>().unwrap_or(1);
+    let content = convert_children(children, ctx).trim().to_string();
+    let title = text::inline_text(children);
+    let marker = "#".repeat(level as usize);
+    if title.is_empty() {
+        return format!("{marker} {content}\n\n");
+    }
+
+    // 经 lib 的转换流程时锚点已预先分配，直接调用转换器时才在此分配
+    let anchor = match toc::assigned_anchor(attrs) {
+        Some(anchor) => anchor.to_string(),
+        None => ctx.anchors.assign(attrs, children, &title),
+    };
+    let heading = if ctx.heading_anchors {
+        format!("{marker} {content} {{#{anchor}}}\n\n")
+    } else {
+        format!("{marker} {content}\n\n")
+    };
+    ctx.headings.push(Heading {
+        level,
+        text: title,
+        anchor,
+    });
+    heading
+}
+
 fn convert_children(children: &[HtmlNode], ctx: &mut ConvertContext) -> String {
     let mut output = String::new();
     for child in children {
-        output.push_str(&convert_node(child, ctx));
+        // 前面只有空格时沿用父节点的行首状态，否则看已输出的内容是否以换行结尾
+        let outer = ctx.at_line_start;
+        let pending = output.trim_end_matches(' ');
+        ctx.at_line_start = if pending.is_empty() {
+            outer
+        } else {
+            pending.ends_with('\n')
+        };
+        let content = convert_node(child, ctx);
+        ctx.at_line_start = outer;
+        // 段落已经结束时，不再叠加独立公式等自带的前导空行
+        let content = if output.ends_with("\n\n") {
+            content.trim_start_matches('\n').to_string()
+        } else {
+            content
+        };
+        // 换行（如 <br>）之后的源码缩进不带入下一行
+        if output.ends_with('\n') {
+            output.push_str(content.trim_start_matches(' '));
+        } else {
+            output.push_str(&content);
+        }
     }
     output
 }
 
-fn convert_list(children: &[HtmlNode], ctx: &mut ConvertContext, ordered: bool) -> String {
-    let list_type = if ordered {
-        ListType::Ordered(1)
+fn convert_list(
+    attrs: &[Attr],
+    children: &[HtmlNode],
+    ctx: &mut ConvertContext,
+    ordered: bool,
+) -> String {
+    let style = if ordered {
+        list_style(attr(attrs, "type").unwrap_or("1"))
     } else {
-        ListType::Unordered
+        ListStyle::Bullet
     };
+    let reversed = ordered && attrs.iter().any(|attr| attr.name == "reversed");
+    let item_count = children
+        .iter()
+        .filter(|child| matches!(child, HtmlNode::Element { tag, .. } if tag == "li"))
+        .count() as i64;
+    let mut number = attr(attrs, "start")
+        .and_then(|value| value.trim().parse::<i64>().ok())
+        .unwrap_or(if reversed { item_count } else { 1 });
+    let step = if reversed { -1 } else { 1 };
 
-    ctx.list_stack.push(list_type);
-    ctx.indent_level += 1;
-    let content = convert_children(children, ctx);
-    ctx.indent_level -= 1;
-    ctx.list_stack.pop();
-    format!("{}\n", content)
+    let mut output = String::new();
+    for child in children {
+        match child {
+            HtmlNode::Element { attrs, .. } if ctx.footnotes.definition_label(attrs).is_some() => {
+                convert_node(child, ctx);
+            }
+            HtmlNode::Element {
+                tag,
+                attrs,
+                children,
+            } if tag == "li" => {
+                // <li value> 重新设定当前及后续项的编号
+                if let Some(value) = attr(attrs, "value").and_then(|v| v.trim().parse().ok()) {
+                    number = value;
+                }
+                output.push_str(&convert_list_item(
+                    &list_marker(style, number),
+                    children,
+                    ctx,
+                ));
+                number += step;
+            }
+            other => {
+                let content = convert_node(other, ctx);
+                if !content.trim().is_empty() {
+                    output.push_str(content.trim());
+                    output.push('\n');
+                }
+            }
+        }
+    }
+
+    if output.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n", output)
+    }
+}
+
+fn list_style(value: &str) -> ListStyle {
+    match value.trim() {
+        "a" => ListStyle::LowerAlpha,
+        "A" => ListStyle::UpperAlpha,
+        "i" => ListStyle::LowerRoman,
+        "I" => ListStyle::UpperRoman,
+        _ => ListStyle::Decimal,
+    }
 }
 
-fn convert_list_item(children: &[HtmlNode], ctx: &mut ConvertContext) -> String {
-    let indent = "  ".repeat(ctx.indent_level.saturating_sub(1));
-    let marker = match ctx.list_stack.last_mut() {
-        Some(ListType::Ordered(num)) => {
-            let current = *num;
-            *num += 1;
-            format!("{}. ", current)
+// Markdown 只支持十进制编号；字母与罗马数字编号输出为无序列表项，并把编号写在正文前
+fn list_marker(style: ListStyle, number: i64) -> String {
+    let label = match style {
+        ListStyle::Bullet => return "- ".to_string(),
+        ListStyle::Decimal => return format!("{}. ", number.max(0)),
+        _ if number <= 0 => number.to_string(),
+        ListStyle::LowerAlpha => alpha_label(number),
+        ListStyle::UpperAlpha => alpha_label(number).to_ascii_uppercase(),
+        ListStyle::LowerRoman => roman_label(number),
+        ListStyle::UpperRoman => roman_label(number).to_ascii_uppercase(),
+    };
+    format!("- {label}. ")
+}
+
+fn alpha_label(mut number: i64) -> String {
+    let mut label = Vec::new();
+    while number > 0 {
+        number -= 1;
+        label.push(b'a' + (number % 26) as u8);
+        number /= 26;
+    }
+    label.reverse();
+    String::from_utf8(label).unwrap_or_default()
+}
+
+fn roman_label(mut number: i64) -> String {
+    const NUMERALS: [(i64, &str); 13] = [
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+    if number >= 4000 {
+        return number.to_string();
+    }
+    let mut label = String::new();
+    for (value, numeral) in NUMERALS {
+        while number >= value {
+            label.push_str(numeral);
+            number -= value;
         }
-        Some(ListType::Unordered) | None => "- ".to_string(),
+    }
+    label
+}
+
+// 列表项内容按块渲染，第二行起按标记宽度缩进，使段落、代码块与嵌套列表归属于该项
+fn convert_list_item(marker: &str, children: &[HtmlNode], ctx: &mut ConvertContext) -> String {
+    let mut content = String::new();
+    for child in children {
+        let rendered = convert_node(child, ctx);
+        let is_block = matches!(
+            child,
+            HtmlNode::Element { tag, .. } if LIST_ITEM_BLOCK_TAGS.contains(&tag.as_str())
+        );
+        if is_block && !content.trim().is_empty() {
+            let rendered = rendered.trim_start();
+            let separator = if INTERRUPTING_PREFIXES
+                .iter()
+                .any(|prefix| rendered.starts_with(prefix))
+            {
+                "\n"
+            } else {
+                "\n\n"
+            };
+            while !content.ends_with(separator) {
+                content.push('\n');
+            }
+            content.push_str(rendered);
+        } else {
+            content.push_str(&rendered);
+        }
+    }
+
+    let checkbox = match task_checkbox(children) {
+        Some(true) => "[x] ",
+        Some(false) => "[ ] ",
+        None => "",
     };
-    let content = convert_children(children, ctx).trim().to_string();
-    format!("{}{}{}\n", indent, marker, content)
+    let indent = " ".repeat(marker.chars().count());
+    let mut lines = content.trim().lines();
+    let mut output = format!("{marker}{checkbox}{}\n", lines.next().unwrap_or_default());
+    for line in lines {
+        if !line.trim().is_empty() {
+            output.push_str(&indent);
+            output.push_str(line);
+        }
+        output.push('\n');
+    }
+    output
+}
+
+// 列表项开头（任何文字之前）的复选框对应 GFM 任务列表
+fn task_checkbox(children: &[HtmlNode]) -> Option<bool> {
+    fn find(nodes: &[HtmlNode]) -> Result<Option<bool>, ()> {
+        for node in nodes {
+            match node {
+                HtmlNode::Text(text) if !text.trim().is_empty() => return Err(()),
+                HtmlNode::Text(_) => {}
+                HtmlNode::Element { tag, attrs, .. }
+                    if tag == "input"
+                        && attr(attrs, "type")
+                            .is_some_and(|kind| kind.eq_ignore_ascii_case("checkbox")) =>
+                {
+                    return Ok(Some(attrs.iter().any(|attr| attr.name == "checked")));
+                }
+                HtmlNode::Element { tag, .. } if tag == "ul" || tag == "ol" => return Err(()),
+                HtmlNode::Element { children, .. } => {
+                    if let Some(checked) = find(children)? {
+                        return Ok(Some(checked));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    find(children).ok().flatten()
+}
+
+// 术语加粗单独成行，定义缩进两格跟在其后；多组之间空一行。
+// 术语之间、术语与第一段定义之间用硬换行，避免渲染时合并为一行；同一术语的多个定义之间空一行
+fn convert_definition_list(children: &[HtmlNode], ctx: &mut ConvertContext) -> String {
+    let mut groups: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut last_was_dd = false;
+
+    for (tag, children) in definition_items(children) {
+        let content = convert_children(children, ctx).trim().to_string();
+        if content.is_empty() {
+            continue;
+        }
+        if tag == "dt" {
+            if last_was_dd {
+                groups.push(std::mem::take(&mut current));
+            } else if !current.is_empty() {
+                current.insert_str(current.len() - 1, "  ");
+            }
+            current.push_str(&format!("**{}**\n", content));
+            last_was_dd = false;
+        } else {
+            if last_was_dd {
+                current.push('\n');
+            } else if !current.is_empty() {
+                current.insert_str(current.len() - 1, "  ");
+            }
+            for line in content.lines() {
+                if !line.trim().is_empty() {
+                    current.push_str("  ");
+                    current.push_str(line);
+                }
+                current.push('\n');
+            }
+            last_was_dd = true;
+        }
+    }
+    if !current.is_empty() {
+        groups.push(current);
+    }
+
+    if groups.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n", groups.join("\n"))
+    }
+}
+
+// <dl> 中的 dt / dd，允许用 <div> 分组
+fn definition_items(children: &[HtmlNode]) -> Vec<(&str, &[HtmlNode])> {
+    let mut items = Vec::new();
+    for child in children {
+        if let HtmlNode::Element { tag, children, .. } = child {
+            match tag.as_str() {
+                "dt" | "dd" => items.push((tag.as_str(), children.as_slice())),
+                "div" => items.extend(definition_items(children)),
+                _ => {}
+            }
+        }
+    }
+    items
+}
+
+// 图注以斜体单独成行，放在图片或代码等内容之后
+fn convert_figure(children: &[HtmlNode], ctx: &mut ConvertContext) -> String {
+    let mut body = String::new();
+    let mut caption = String::new();
+    for child in children {
+        match child {
+            HtmlNode::Element { tag, children, .. } if tag == "figcaption" => {
+                caption = convert_children(children, ctx).trim().to_string();
+            }
+            other => body.push_str(&convert_node(other, ctx)),
+        }
+    }
+
+    let mut parts = Vec::new();
+    if !body.trim().is_empty() {
+        parts.push(body.trim().to_string());
+    }
+    if !caption.is_empty() {
+        parts.push(wrap_inline("*", &caption));
+    }
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n\n", parts.join("\n\n"))
+    }
+}
+
+// <summary> 作为四级标题，其后是展开后的内容
+fn convert_details(children: &[HtmlNode], ctx: &mut ConvertContext) -> String {
+    let mut summary = String::new();
+    let mut body = String::new();
+    for child in children {
+        match child {
+            HtmlNode::Element { tag, children, .. } if tag == "summary" && summary.is_empty() => {
+                summary = convert_children(children, ctx).trim().to_string();
+            }
+            other => body.push_str(&convert_node(other, ctx)),
+        }
+    }
+
+    let mut output = String::new();
+    if !summary.is_empty() {
+        // 用粗体而非标题：summary 不在 ctx.headings 中，输出 #### 会让目录与章节截取不一致
+        output.push_str(&format!("**{}**\n\n", summary));
+    }
+    let body = body.trim();
+    if !body.is_empty() {
+        output.push_str(body);
+        output.push_str("\n\n");
+    }
+    output
+}
+
+// 元素内只有脚注引用链接（及空白）
+fn is_footnote_reference_group(children: &[HtmlNode], ctx: &ConvertContext) -> bool {
+    let mut found = false;
+    for child in children {
+        match child {
+            HtmlNode::Text(text) if text.trim().is_empty() => {}
+            HtmlNode::Element { tag, attrs, .. }
+                if tag == "a" && ctx.footnotes.reference_label(attrs).is_some() =>
+            {
+                found = true;
+            }
+            _ => return false,
+        }
+    }
+    found
+}
+
+// 行内标记包裹内容；首尾空白留在标记外，避免生成无效的强调
+fn wrap_inline(marker: &str, content: &str) -> String {
+    let trimmed = content.trim();
+    if trimmed.is_empty() {
+        return content.to_string();
+    }
+    let leading = &content[..content.len() - content.trim_start().len()];
+    let trailing = &content[content.trim_end().len()..];
+    format!("{leading}{marker}{trimmed}{marker}{trailing}")
+}
+
+// 缩写的全称、时间的机器可读值在与正文不同时以括号附在后面
+fn with_annotation(content: String, annotation: Option<String>) -> String {
+    let Some(annotation) = annotation else {
+        return content;
+    };
+    if content.trim() == annotation {
+        return content;
+    }
+    if content.trim().is_empty() {
+        return annotation;
+    }
+    format!("{} ({})", content, annotation)
 }
 
 fn convert_table(children: &[HtmlNode], ctx: &mut ConvertContext) -> String {
@@ -187,12 +782,324 @@ fn convert_table(children: &[HtmlNode], ctx: &mut ConvertContext) -> String {
     output
 }
 
+fn convert_code_block(attrs: &[Attr], children: &[HtmlNode], ctx: &ConvertContext) -> String {
+    let inner_language = children.iter().find_map(|child| match child {
+        HtmlNode::Element { tag, attrs, .. } if tag == "code" => code_language(attrs),
+        _ => None,
+    });
+    let language = code_language(attrs)
+        .or(inner_language)
+        .or_else(|| ctx.code_language.clone())
+        .unwrap_or_default();
+
+    let code = code_text(children);
+    let code = code.trim_matches('\n').trim_end();
+    format!("{}\n\n", fenced_code(code, &language))
+}
+
+// 围栏代码块；代码本身含有反引号时围栏比其中最长的连续反引号多一个
+pub fn fenced_code(code: &str, language: &str) -> String {
+    let fence = "`".repeat((longest_backtick_run(code) + 1).max(3));
+    format!("{fence}{language}\n{code}\n{fence}")
+}
+
+// 行内代码的反引号数量多于内容中最长的连续反引号；内容以反引号开头或结尾时两侧补空格
+// 表格单元格中的行内代码不能含换行，| 需转义为 \|（GFM 表格在代码中同样识别该转义）
+fn code_span(children: &[HtmlNode], ctx: &ConvertContext) -> String {
+    let code = code_text(children);
+    if ctx.in_table {
+        inline_code(collapse_whitespace(&code).trim()).replace('|', "\\|")
+    } else {
+        inline_code(&code)
+    }
+}
+
+fn inline_code(code: &str) -> String {
+    let delimiter = "`".repeat(longest_backtick_run(code) + 1);
+    if code.starts_with('`') || code.ends_with('`') {
+        format!("{delimiter} {code} {delimiter}")
+    } else {
+        format!("{delimiter}{code}{delimiter}")
+    }
+}
+
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|ch| ch != '`').map(str::len).max().unwrap_or(0)
+}
+
+// 读取代码的原始文本：忽略高亮用的标签，去掉行号栏与按钮；
+// 按行包裹的块级元素（如 <div class="line">）之间补换行
+fn code_text(children: &[HtmlNode]) -> String {
+    fn append(nodes: &[HtmlNode], output: &mut String) {
+        for node in nodes {
+            match node {
+                HtmlNode::Text(text) => output.push_str(text),
+                HtmlNode::Element {
+                    tag,
+                    attrs,
+                    children,
+                } => {
+                    if is_gutter(attrs)
+                        || is_copy_button(tag, attrs)
+                        || matches!(tag.as_str(), "button" | "script" | "style")
+                    {
+                        continue;
+                    }
+                    if tag == "br" {
+                        output.push('\n');
+                        continue;
+                    }
+                    append(children, output);
+                    if matches!(tag.as_str(), "div" | "p" | "li" | "tr") && !output.ends_with('\n')
+                    {
+                        output.push('\n');
+                    }
+                }
+            }
+        }
+    }
+
+    let mut output = String::new();
+    append(children, &mut output);
+    output
+}
+
+// 从 class（language-*、lang-*、highlight-source-*、brush: *）或 data-lang / data-language 中识别代码语言
+fn code_language(attrs: &[Attr]) -> Option<String> {
+    let from_data = attrs
+        .iter()
+        .find(|attr| attr.name == "data-lang" || attr.name == "data-language")
+        .map(|attr| attr.value.as_str());
+    let from_class = attrs
+        .iter()
+        .find(|attr| attr.name == "class")
+        .and_then(|attr| {
+            let mut tokens = attr.value.split_whitespace();
+            while let Some(token) = tokens.next() {
+                for prefix in ["language-", "lang-", "highlight-source-"] {
+                    if let Some(language) = token.strip_prefix(prefix) {
+                        return Some(language);
+                    }
+                }
+                if token == "brush:" {
+                    return tokens.next();
+                }
+                if let Some(language) = token.strip_prefix("brush:") {
+                    return Some(language.trim_end_matches(';'));
+                }
+            }
+            None
+        });
+
+    let language: String = from_data
+        .or(from_class)?
+        .trim_end_matches(';')
+        .to_ascii_lowercase()
+        .chars()
+        .filter(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '-' | '#' | '_' | '.'))
+        .collect();
+    if language.is_empty() || PLAIN_LANGUAGES.contains(&language.as_str()) {
+        None
+    } else {
+        Some(language)
+    }
+}
+
+// pre / code 以外的元素只有包裹着 pre 时，语言类名才表示代码语言；
+// 否则 <div class="lang-en">、<span data-lang="fr"> 之类的多语言标记会被误当作代码块
+fn container_language(attrs: &[Attr], children: &[HtmlNode]) -> Option<String> {
+    code_language(attrs).filter(|_| contains_pre(children))
+}
+
+fn contains_pre(nodes: &[HtmlNode]) -> bool {
+    nodes.iter().any(|node| match node {
+        HtmlNode::Element { tag, children, .. } => tag == "pre" || contains_pre(children),
+        HtmlNode::Text(_) => false,
+    })
+}
+
+fn class_tokens(attrs: &[Attr]) -> impl Iterator<Item = String> + '_ {
+    parser::classes(attrs).map(str::to_ascii_lowercase)
+}
+
+fn is_gutter(attrs: &[Attr]) -> bool {
+    attrs.iter().any(|attr| attr.name == "data-line-number")
+        || class_tokens(attrs).any(|class| GUTTER_CLASSES.contains(&class.as_str()))
+}
+
+// 按 - / _ 拆分 class 后逐段比较，避免 copyright 之类的类名被当作复制按钮
+fn is_copy_button(tag: &str, attrs: &[Attr]) -> bool {
+    let has_part = |words: &[&str]| {
+        class_tokens(attrs).any(|class| class.split(['-', '_']).any(|part| words.contains(&part)))
+    };
+    tag == "clipboard-copy"
+        || ((tag == "button" || has_part(&["btn", "button"])) && has_part(&COPY_MARKERS))
+}
+
+fn is_code_container(tag: &str, attrs: &[Attr], children: &[HtmlNode]) -> bool {
+    tag == "pre"
+        || container_language(attrs, children).is_some()
+        || class_tokens(attrs).any(|class| CODE_CONTAINER_CLASSES.contains(&class.as_str()))
+        || (class_tokens(attrs)
+            .any(|class| GENERIC_CODE_CONTAINER_CLASSES.contains(&class.as_str()))
+            && contains_pre(children))
+        || (tag == "table" && is_gutter_table(children))
+}
+
+// 行号栏表格：某一行的单元格本身是行号栏（如 Pygments 的 <td class="linenos">）
+fn is_gutter_table(nodes: &[HtmlNode]) -> bool {
+    nodes.iter().any(|node| match node {
+        HtmlNode::Element { tag, children, .. } if tag == "tr" => children.iter().any(|cell| {
+            matches!(cell, HtmlNode::Element { tag, attrs, .. }
+                if (tag == "td" || tag == "th") && is_gutter(attrs))
+        }),
+        HtmlNode::Element { tag, children, .. }
+            if matches!(tag.as_str(), "thead" | "tbody" | "tfoot") =>
+        {
+            is_gutter_table(children)
+        }
+        _ => false,
+    })
+}
+
 fn process_text(text: &str, ctx: &ConvertContext) -> String {
-    if ctx.in_code_block {
-        text.to_string()
-    } else if ctx.in_table {
-        text.replace('|', "\\|")
+    if ctx.in_table {
+        let escaped = escape_text(text, ctx.escape, ctx.at_line_start);
+        // strict 模式已转义 |
+        match ctx.escape {
+            EscapeMode::Minimal => escaped.replace('|', "\\|"),
+            EscapeMode::Strict => escaped,
+        }
     } else {
-        text.replace('\n', " ").replace("  ", " ")
+        escape_text(&collapse_whitespace(text), ctx.escape, ctx.at_line_start)
+    }
+}
+
+// 连续空白（含换行）压缩为单个空格
+fn collapse_whitespace(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut last_space = false;
+    for ch in text.chars() {
+        if ch.is_whitespace() {
+            if !last_space {
+                output.push(' ');
+            }
+            last_space = true;
+        } else {
+            output.push(ch);
+            last_space = false;
+        }
     }
+    output
+}
+
+// 转义文本节点中的 Markdown 特殊字符。文本位于行首时转义开头的块级标记
+// （# 标题、> 引用、- / + / * / 1. 列表、分隔线）；行内字符按所在位置判断
+fn escape_text(text: &str, mode: EscapeMode, at_line_start: bool) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let line_marker = if at_line_start {
+        line_start_marker(&chars)
+    } else {
+        None
+    };
+    let mut output = String::with_capacity(text.len() + 8);
+
+    for (idx, &ch) in chars.iter().enumerate() {
+        let prev = idx.checked_sub(1).map(|i| chars[i]);
+        let next = chars.get(idx + 1).copied();
+        let escape = Some(idx) == line_marker
+            || match mode {
+                EscapeMode::Strict => {
+                    matches!(
+                        ch,
+                        '\\' | '`'
+                            | '*'
+                            | '_'
+                            | '['
+                            | ']'
+                            | '<'
+                            | '>'
+                            | '#'
+                            | '~'
+                            | '|'
+                            | '&'
+                            | '$'
+                    )
+                }
+                EscapeMode::Minimal => needs_minimal_escape(&chars, idx, prev, next),
+            };
+        if escape {
+            output.push('\\');
+        }
+        output.push(ch);
+    }
+    output
+}
+
+fn needs_minimal_escape(
+    chars: &[char],
+    idx: usize,
+    prev: Option<char>,
+    next: Option<char>,
+) -> bool {
+    let is_space = |ch: Option<char>| ch.is_some_and(char::is_whitespace);
+    let is_word = |ch: Option<char>| ch.is_some_and(char::is_alphanumeric);
+    match chars[idx] {
+        '`' => true,
+        // 两侧都是空白时不会构成强调，例如 "2 * 3"
+        '*' | '~' => !(is_space(prev) && is_space(next)),
+        // 单词内部的下划线不会构成强调，例如 snake_case
+        '_' => !((is_word(prev) && is_word(next)) || (is_space(prev) && is_space(next))),
+        // 后面有配对的 ] 时可能构成链接或脚注引用
+        '[' => chars[idx + 1..].contains(&']'),
+        '\\' => next.is_none_or(|ch| ch.is_ascii_punctuation()),
+        // 可能构成 HTML 标签或自动链接
+        '<' => next.is_some_and(|ch| ch.is_ascii_alphabetic() || matches!(ch, '/' | '!' | '?')),
+        // 可能构成字符实体，例如字面量 "&amp;"
+        '&' => is_entity_like(&chars[idx + 1..]),
+        // 同一行后面还有 $ 时可能被解析为行内公式，例如 "$5 and $10"
+        '$' => chars[idx + 1..]
+            .iter()
+            .take_while(|ch| **ch != '\n')
+            .any(|ch| *ch == '$'),
+        _ => false,
+    }
+}
+
+// 返回行首块级标记中需要转义的字符位置
+fn line_start_marker(chars: &[char]) -> Option<usize> {
+    let start = chars.iter().position(|ch| !ch.is_whitespace())?;
+    let rest = &chars[start..];
+    let followed_by_space = |len: usize| rest.get(len).is_none_or(|ch| ch.is_whitespace());
+
+    match rest[0] {
+        '#' => {
+            let hashes = rest.iter().take_while(|ch| **ch == '#').count();
+            (hashes <= 6 && followed_by_space(hashes)).then_some(start)
+        }
+        // 行首的 > 不论后面是否有空格都会开始引用块
+        '>' => Some(start),
+        '-' | '+' | '*' if followed_by_space(1) => Some(start),
+        // 分隔线或 Setext 标题下划线，例如 "---"、"==="
+        '-' | '*' | '_' | '=' if rest.iter().all(|ch| *ch == rest[0] || ch.is_whitespace()) => {
+            Some(start)
+        }
+        '0'..='9' => {
+            let digits = rest.iter().take_while(|ch| ch.is_ascii_digit()).count();
+            (digits <= 9
+                && matches!(rest.get(digits), Some('.' | ')'))
+                && followed_by_space(digits + 1))
+            .then_some(start + digits)
+        }
+        _ => None,
+    }
+}
+
+fn is_entity_like(rest: &[char]) -> bool {
+    let body = rest.strip_prefix(&['#']).unwrap_or(rest);
+    let len = body
+        .iter()
+        .take_while(|ch| ch.is_ascii_alphanumeric())
+        .count();
+    len > 0 && body.get(len) == Some(&';')
 }
@@ -0,0 +1,181 @@
+use std::collections::HashSet;
+
+use futures::{StreamExt, stream};
+use regex::Regex;
+use rmcp::schemars;
+use serde::Serialize;
+use url::Url;
+
+use crate::{
+    fetch_error::FetchError,
+    fetcher::{self, FetchConfig, FetchOptions, FetchedDocument},
+    parser, urls,
+};
+
+pub const DEFAULT_MAX_DEPTH: usize = 2;
+pub const DEFAULT_MAX_PAGES: usize = 20;
+pub const MAX_PAGES_LIMIT: usize = 100;
+// 同时抓取的页面数；直接请求失败时会回退到 Selenium，需限制同时打开的浏览器会话
+const MAX_CONCURRENT_FETCHES: usize = 4;
+
+#[derive(Debug, Clone, Default)]
+pub struct CrawlScope {
+    pub same_host: bool,
+    pub path_prefix: Option<String>,
+    pub pattern: Option<Regex>,
+}
+
+impl CrawlScope {
+    fn allows(&self, url: &Url, seed_hosts: &HashSet<String>) -> bool {
+        if !matches!(url.scheme(), "http" | "https") {
+            return false;
+        }
+        if self.same_host && !url.host_str().is_some_and(|host| seed_hosts.contains(host)) {
+            return false;
+        }
+        if let Some(prefix) = &self.path_prefix
+            && !url.path().starts_with(prefix.as_str())
+        {
+            return false;
+        }
+        self.pattern
+            .as_ref()
+            .is_none_or(|pattern| pattern.is_match(url.as_str()))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CrawlLimits {
+    pub max_depth: usize,
+    pub max_pages: usize,
+}
+
+impl Default for CrawlLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_pages: DEFAULT_MAX_PAGES,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, schemars::JsonSchema)]
+pub struct CrawlEdge {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug)]
+pub struct CrawledPage {
+    pub url: String,
+    pub depth: usize,
+    pub document: Result<FetchedDocument, FetchError>,
+}
+
+#[derive(Debug, Default)]
+pub struct CrawlResult {
+    pub pages: Vec<CrawledPage>,
+    pub edges: Vec<CrawlEdge>,
+    // 因 max_pages 限制仍有未抓取的范围内链接时为 true
+    pub truncated: bool,
+}
+
+// 去重用的规范化地址：去掉片段、空查询串，scheme 与 host 统一小写，省略默认端口
+pub fn normalize_url(url: &str) -> Option<Url> {
+    let mut parsed = Url::parse(url.trim()).ok()?;
+    parsed.set_fragment(None);
+    if parsed.query() == Some("") {
+        parsed.set_query(None);
+    }
+    Some(parsed)
+}
+
+pub async fn crawl(
+    config: &FetchConfig,
+    options: &FetchOptions,
+    seeds: &[String],
+    scope: &CrawlScope,
+    limits: CrawlLimits,
+) -> CrawlResult {
+    let mut result = CrawlResult::default();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut edges: HashSet<CrawlEdge> = HashSet::new();
+
+    let seed_hosts: HashSet<String> = seeds
+        .iter()
+        .filter_map(|seed| normalize_url(seed))
+        .filter_map(|seed| seed.host_str().map(|host| host.to_string()))
+        .collect();
+
+    // 种子地址总会被抓取，范围条件只作用于之后发现的链接
+    let mut frontier: Vec<String> = Vec::new();
+    for seed in seeds {
+        let key = normalize_url(seed)
+            .map(|url| url.to_string())
+            .unwrap_or_else(|| seed.clone());
+        if seen.insert(key.clone()) {
+            frontier.push(key);
+        }
+    }
+
+    let mut depth = 0;
+    while !frontier.is_empty() {
+        let remaining = limits.max_pages.saturating_sub(result.pages.len());
+        if frontier.len() > remaining {
+            frontier.truncate(remaining);
+            result.truncated = true;
+        }
+        if frontier.is_empty() {
+            break;
+        }
+
+        let fetches: Vec<_> = frontier
+            .iter()
+            .map(|url| fetcher::fetch_document(config, url, options))
+            .collect();
+        let documents: Vec<_> = stream::iter(fetches)
+            .buffered(MAX_CONCURRENT_FETCHES)
+            .collect()
+            .await;
+        let mut next: Vec<String> = Vec::new();
+
+        for (url, document) in frontier.into_iter().zip(documents) {
+            if depth < limits.max_depth
+                && let Ok(FetchedDocument::Html(html)) = &document
+            {
+                let dom = parser::parse_html(html);
+                for link in urls::extract_links(&dom, &url) {
+                    let Some(target) = normalize_url(&link) else {
+                        continue;
+                    };
+                    if !scope.allows(&target, &seed_hosts) {
+                        continue;
+                    }
+
+                    let target = target.to_string();
+                    let edge = CrawlEdge {
+                        from: url.clone(),
+                        to: target.clone(),
+                    };
+                    if target != url && edges.insert(edge.clone()) {
+                        result.edges.push(edge);
+                    }
+                    if seen.insert(target.clone()) {
+                        next.push(target);
+                    }
+                }
+            }
+
+            result.pages.push(CrawledPage {
+                url,
+                depth,
+                document,
+            });
+        }
+
+        frontier = next;
+        depth += 1;
+    }
+
+    result
+}
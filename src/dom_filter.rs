@@ -0,0 +1,209 @@
+use crate::{
+    math::{self, MathNode},
+    parser::{Attr, HtmlNode},
+};
+
+// 常见 CMP（OneTrust、Quantcast、Didomi、Cookiebot 等）与通用 Cookie 横幅使用的 id / class；
+// 按完整的 token 匹配，或作为 token 的前缀（后接 - / _），例如 onetrust-banner-sdk
+const CONSENT_MARKERS: [&str; 20] = [
+    "onetrust",
+    "ot-sdk",
+    "qc-cmp",
+    "qc-cmp2",
+    "didomi",
+    "cookiebot",
+    "cybotcookiebotdialog",
+    "usercentrics",
+    "sp_message",
+    "cc-window",
+    "cc-banner",
+    "cookie-banner",
+    "cookie-notice",
+    "cookie-law",
+    "cookie-consent",
+    "cookieconsent",
+    "consent-banner",
+    "consent-modal",
+    "gdpr-banner",
+    "gdpr-consent",
+];
+
+// 固定定位元素带有这些 class 片段（按 - / _ 拆分后比较）时视为浮层
+const OVERLAY_CLASS_PARTS: [&str; 7] = [
+    "modal",
+    "popup",
+    "overlay",
+    "backdrop",
+    "lightbox",
+    "newsletter",
+    "interstitial",
+];
+
+// 固定定位的浮层若包含这些正文容器，则视为页面主体而非弹窗
+const CONTENT_TAGS: [&str; 2] = ["main", "article"];
+
+// 浏览器模式下根据计算样式标记的不可见元素
+pub const HIDDEN_MARKER_ATTR: &str = "data-cleanfetch-hidden";
+
+// 去掉浏览器序列化结果中的 HIDDEN_MARKER_ATTR 标记，用于原样返回 HTML 的场景；
+// 序列化脚本总是输出 name="value" 的形式
+pub fn remove_hidden_markers(html: &str) -> String {
+    html.replace(&format!(" {HIDDEN_MARKER_ATTR}=\"true\""), "")
+}
+
+// 屏幕阅读器专用文本常用的 class
+const SR_ONLY_CLASSES: [&str; 5] = [
+    "sr-only",
+    "visually-hidden",
+    "visuallyhidden",
+    "screen-reader-text",
+    "a11y-hidden",
+];
+
+// 移除 Cookie 同意弹窗与固定定位的弹窗类浮层（cleanfetch 的 drop_overlays）
+pub fn strip_overlays(nodes: Vec<HtmlNode>) -> Vec<HtmlNode> {
+    nodes
+        .into_iter()
+        .filter_map(|node| match node {
+            HtmlNode::Element {
+                tag,
+                attrs,
+                children,
+            } => {
+                if is_overlay(&tag, &attrs, &children) {
+                    None
+                } else {
+                    Some(HtmlNode::Element {
+                        tag,
+                        attrs,
+                        children: strip_overlays(children),
+                    })
+                }
+            }
+            text => Some(text),
+        })
+        .collect()
+}
+
+fn is_overlay(tag: &str, attrs: &[Attr], children: &[HtmlNode]) -> bool {
+    if matches!(tag, "html" | "body" | "main" | "article") {
+        return false;
+    }
+    has_consent_marker(attrs)
+        || (is_fixed_position(attrs) && looks_like_overlay(attrs) && !contains_content(children))
+}
+
+fn has_consent_marker(attrs: &[Attr]) -> bool {
+    tokens(attrs, &["id", "class"]).any(|token| {
+        CONSENT_MARKERS.iter().any(|marker| {
+            token
+                .strip_prefix(marker)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(['-', '_']))
+        })
+    })
+}
+
+// 固定定位本身很常见（顶栏、侧边栏、应用外壳），还需带有对话框语义或弹窗类名
+fn looks_like_overlay(attrs: &[Attr]) -> bool {
+    let attr = |name: &str| {
+        attrs
+            .iter()
+            .find(|attr| attr.name == name)
+            .map(|attr| attr.value.trim().to_ascii_lowercase())
+    };
+    matches!(attr("role").as_deref(), Some("dialog" | "alertdialog"))
+        || attr("aria-modal").as_deref() == Some("true")
+        || tokens(attrs, &["id", "class"]).any(|token| {
+            token
+                .split(['-', '_'])
+                .any(|part| OVERLAY_CLASS_PARTS.contains(&part))
+        })
+}
+
+// id / class 等属性按空白拆分并转小写后的 token
+fn tokens<'a>(attrs: &'a [Attr], names: &'a [&str]) -> impl Iterator<Item = String> + 'a {
+    attrs
+        .iter()
+        .filter(move |attr| names.contains(&attr.name.as_str()))
+        .flat_map(|attr| attr.value.split_whitespace())
+        .map(str::to_ascii_lowercase)
+}
+
+fn is_fixed_position(attrs: &[Attr]) -> bool {
+    attrs
+        .iter()
+        .find(|attr| attr.name == "style")
+        .is_some_and(|style| compact_style(&style.value).contains("position:fixed"))
+}
+
+fn contains_content(children: &[HtmlNode]) -> bool {
+    children.iter().any(|child| match child {
+        HtmlNode::Element { tag, children, .. } => {
+            CONTENT_TAGS.contains(&tag.as_str()) || contains_content(children)
+        }
+        HtmlNode::Text(_) => false,
+    })
+}
+
+// 移除视觉上不可见的节点：hidden 属性（until-found 除外）、aria-hidden="true"、display:none / visibility:hidden、
+// 仅供屏幕阅读器的文本以及 <template> 内容
+pub fn strip_hidden(nodes: Vec<HtmlNode>) -> Vec<HtmlNode> {
+    nodes
+        .into_iter()
+        .filter_map(|node| match node {
+            HtmlNode::Element {
+                tag,
+                attrs,
+                children,
+            } => {
+                // 维基百科的公式图片带 aria-hidden，但它是公式源码的来源，保留给转换器识别
+                if is_hidden(&tag, &attrs)
+                    && !matches!(
+                        math::classify(&tag, &attrs, &children),
+                        Some(MathNode::Formula(_))
+                    )
+                {
+                    None
+                } else {
+                    Some(HtmlNode::Element {
+                        tag,
+                        attrs,
+                        children: strip_hidden(children),
+                    })
+                }
+            }
+            text => Some(text),
+        })
+        .collect()
+}
+
+fn is_hidden(tag: &str, attrs: &[Attr]) -> bool {
+    if tag == "template" {
+        return true;
+    }
+
+    attrs.iter().any(|attr| match attr.name.as_str() {
+        // hidden="until-found" 的内容可被页内搜索展开，仍视为可见
+        "hidden" => !attr.value.trim().eq_ignore_ascii_case("until-found"),
+        HIDDEN_MARKER_ATTR => true,
+        "aria-hidden" => attr.value.trim().eq_ignore_ascii_case("true"),
+        "style" => {
+            let compact = compact_style(&attr.value);
+            compact.contains("display:none") || compact.contains("visibility:hidden")
+        }
+        "class" => attr
+            .value
+            .split_whitespace()
+            .any(|class| SR_ONLY_CLASSES.contains(&class.to_ascii_lowercase().as_str())),
+        _ => false,
+    })
+}
+
+// 去掉空白并转小写，便于匹配 "display: none" 之类的内联样式
+fn compact_style(style: &str) -> String {
+    style
+        .chars()
+        .filter(|ch| !ch.is_whitespace())
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+
+use rmcp::schemars;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::{parser::HtmlNode, selector::Selector, text, urls};
+
+// 这些属性的值按页面地址解析为绝对 URL
+const URL_ATTRS: [&str; 5] = ["href", "src", "action", "poster", "data-src"];
+// 嵌套层数上限，防止过深的映射
+const MAX_NESTING: usize = 8;
+
+// 字段映射：可以直接写选择器字符串，也可以写完整的字段定义
+#[derive(Debug, Clone, Deserialize, schemars::JsonSchema)]
+#[serde(untagged)]
+pub enum ExtractField {
+    Selector(String),
+    Spec(FieldSpec),
+}
+
+#[derive(Debug, Clone, Default, Deserialize, schemars::JsonSchema)]
+pub struct FieldSpec {
+    #[serde(default)]
+    #[schemars(description = "可选：相对于当前元素的 CSS 选择器，省略时取当前元素本身")]
+    pub selector: Option<String>,
+    #[serde(default)]
+    #[schemars(
+        description = "可选：读取的属性名，省略时取元素的文本内容；href / src 等链接属性会解析为绝对地址"
+    )]
+    pub attr: Option<String>,
+    #[serde(default)]
+    #[schemars(
+        description = "可选：为 true 时返回所有匹配组成的数组，否则只取第一个匹配，默认 false"
+    )]
+    pub list: bool,
+    #[serde(default)]
+    #[schemars(description = "可选：嵌套字段映射，设置后每个匹配元素输出为一个对象（忽略 attr）")]
+    pub fields: Option<BTreeMap<String, ExtractField>>,
+}
+
+// 解析后的映射，选择器在抓取前全部校验
+#[derive(Debug, Clone)]
+pub struct ExtractPlan {
+    root: Option<Selector>,
+    fields: Vec<(String, CompiledField)>,
+}
+
+#[derive(Debug, Clone)]
+struct CompiledField {
+    selector: Option<Selector>,
+    attr: Option<String>,
+    list: bool,
+    fields: Option<Vec<(String, CompiledField)>>,
+}
+
+impl ExtractPlan {
+    pub fn compile(
+        root: Option<&str>,
+        fields: &BTreeMap<String, ExtractField>,
+    ) -> Result<Self, String> {
+        if fields.is_empty() {
+            return Err("fields must not be empty".to_string());
+        }
+        let root = root
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| Selector::parse(v).map_err(|e| format!("root: {e}")))
+            .transpose()?;
+        Ok(Self {
+            root,
+            fields: compile_fields(fields, "", 0)?,
+        })
+    }
+
+    // 设置了 root 时每个匹配元素生成一条记录，否则整页生成一条记录
+    pub fn run(&self, nodes: &[HtmlNode], base_url: &str) -> Value {
+        match &self.root {
+            Some(root) => Value::Array(
+                root.select(nodes)
+                    .into_iter()
+                    .map(|node| extract_record(&self.fields, node, base_url))
+                    .collect(),
+            ),
+            None => extract_record_in(&self.fields, nodes, base_url),
+        }
+    }
+}
+
+fn compile_fields(
+    fields: &BTreeMap<String, ExtractField>,
+    path: &str,
+    depth: usize,
+) -> Result<Vec<(String, CompiledField)>, String> {
+    if depth >= MAX_NESTING {
+        return Err(format!("fields nested deeper than {MAX_NESTING} levels"));
+    }
+
+    fields
+        .iter()
+        .map(|(name, field)| {
+            let field_path = if path.is_empty() {
+                name.clone()
+            } else {
+                format!("{path}.{name}")
+            };
+            let spec = match field {
+                ExtractField::Selector(selector) => FieldSpec {
+                    selector: Some(selector.clone()),
+                    ..Default::default()
+                },
+                ExtractField::Spec(spec) => spec.clone(),
+            };
+
+            let selector = spec
+                .selector
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(|v| Selector::parse(v).map_err(|e| format!("{field_path}: {e}")))
+                .transpose()?;
+            let nested = match &spec.fields {
+                Some(nested) if nested.is_empty() => {
+                    return Err(format!("{field_path}: fields must not be empty"));
+                }
+                Some(nested) => Some(compile_fields(nested, &field_path, depth + 1)?),
+                None => None,
+            };
+            let attr = spec
+                .attr
+                .map(|v| v.trim().to_ascii_lowercase())
+                .filter(|v| !v.is_empty());
+
+            Ok((
+                name.clone(),
+                CompiledField {
+                    selector,
+                    attr,
+                    list: spec.list,
+                    fields: nested,
+                },
+            ))
+        })
+        .collect()
+}
+
+fn extract_record(fields: &[(String, CompiledField)], node: &HtmlNode, base_url: &str) -> Value {
+    let mut record = Map::new();
+    for (name, field) in fields {
+        record.insert(name.clone(), extract_field(field, node, base_url));
+    }
+    Value::Object(record)
+}
+
+// 顶层没有当前元素，字段直接在整个文档中查找
+fn extract_record_in(
+    fields: &[(String, CompiledField)],
+    nodes: &[HtmlNode],
+    base_url: &str,
+) -> Value {
+    let mut record = Map::new();
+    for (name, field) in fields {
+        let value = match &field.selector {
+            Some(selector) => collect_values(field, selector.select(nodes), base_url),
+            None => match &field.fields {
+                Some(nested) => extract_record_in(nested, nodes, base_url),
+                None if field.attr.is_none() => text_value(nodes),
+                None => Value::Null,
+            },
+        };
+        record.insert(name.clone(), value);
+    }
+    Value::Object(record)
+}
+
+fn extract_field(field: &CompiledField, node: &HtmlNode, base_url: &str) -> Value {
+    match &field.selector {
+        Some(selector) => collect_values(field, selector.select(node_children(node)), base_url),
+        // 未指定选择器时作用于当前元素本身
+        None => collect_values(field, vec![node], base_url),
+    }
+}
+
+// list 为 true 时返回所有非空值，否则返回第一个非空值
+fn collect_values(field: &CompiledField, matches: Vec<&HtmlNode>, base_url: &str) -> Value {
+    let mut values = matches
+        .into_iter()
+        .map(|node| match (&field.fields, &field.attr) {
+            (Some(nested), _) => extract_record(nested, node, base_url),
+            (None, Some(attr)) => {
+                read_attr(node, attr, base_url).map_or(Value::Null, Value::String)
+            }
+            (None, None) => text_value(node_children(node)),
+        })
+        .filter(|value| !value.is_null());
+
+    if field.list {
+        Value::Array(values.collect())
+    } else {
+        values.next().unwrap_or(Value::Null)
+    }
+}
+
+fn text_value(nodes: &[HtmlNode]) -> Value {
+    let text = text::inline_text(nodes);
+    if text.is_empty() {
+        Value::Null
+    } else {
+        Value::String(text)
+    }
+}
+
+fn node_children(node: &HtmlNode) -> &[HtmlNode] {
+    match node {
+        HtmlNode::Element { children, .. } => children,
+        HtmlNode::Text(_) => &[],
+    }
+}
+
+fn read_attr(node: &HtmlNode, name: &str, base_url: &str) -> Option<String> {
+    let HtmlNode::Element { attrs, .. } = node else {
+        return None;
+    };
+    let value = attrs.iter().find(|attr| attr.name == name)?.value.trim();
+    if URL_ATTRS.contains(&name) {
+        return urls::resolve_href(value, base_url).or_else(|| Some(value.to_string()));
+    }
+    Some(value.to_string())
+}
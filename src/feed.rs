@@ -0,0 +1,186 @@
+use rmcp::schemars;
+use serde::Serialize;
+
+use crate::{
+    html_to_markdown,
+    parser::HtmlNode,
+    urls::resolve_href,
+    xml::{self, XmlNode},
+};
+
+const FEED_MIME_TYPES: [&str; 2] = ["application/rss+xml", "application/atom+xml"];
+
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct Feed {
+    pub feed_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    pub entries: Vec<FeedEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct FeedEntry {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+pub fn parse_feed(input: &str, feed_url: &str) -> Result<Feed, String> {
+    let nodes = xml::parse_xml(input)?;
+    let root = xml::root_element(&nodes).ok_or_else(|| "empty xml document".to_string())?;
+
+    match root.local_name().as_deref() {
+        Some("rss") => {
+            let channel = root
+                .child_element("channel")
+                .ok_or_else(|| "rss feed without channel".to_string())?;
+            Ok(parse_rss(channel, channel, feed_url))
+        }
+        // RSS 1.0：item 与 channel 同级
+        Some("rdf") => {
+            let channel = root
+                .child_element("channel")
+                .ok_or_else(|| "rdf feed without channel".to_string())?;
+            Ok(parse_rss(channel, root, feed_url))
+        }
+        Some("feed") => Ok(parse_atom(root, feed_url)),
+        _ => Err("not an rss or atom feed".to_string()),
+    }
+}
+
+// 从 HTML 的 <link rel="alternate" type="application/rss+xml"> 中发现订阅地址
+pub fn discover_feed_urls(nodes: &[HtmlNode], base_url: &str) -> Vec<String> {
+    let mut found = Vec::new();
+    for node in nodes {
+        collect_feed_links(node, base_url, &mut found);
+    }
+    found
+}
+
+fn collect_feed_links(node: &HtmlNode, base_url: &str, found: &mut Vec<String>) {
+    if let HtmlNode::Element {
+        tag,
+        attrs,
+        children,
+    } = node
+    {
+        if tag == "link" {
+            let attr = |name: &str| {
+                attrs
+                    .iter()
+                    .find(|attr| attr.name == name)
+                    .map(|attr| attr.value.trim().to_ascii_lowercase())
+            };
+            let is_alternate = attr("rel")
+                .is_some_and(|rel| rel.split_whitespace().any(|token| token == "alternate"));
+            let is_feed_type =
+                attr("type").is_some_and(|ty| FEED_MIME_TYPES.iter().any(|mime| *mime == ty));
+
+            if is_alternate
+                && is_feed_type
+                && let Some(href) = attrs.iter().find(|attr| attr.name == "href")
+                && let Some(resolved) = resolve_href(&href.value, base_url)
+                && !found.contains(&resolved)
+            {
+                found.push(resolved);
+            }
+        }
+
+        for child in children {
+            collect_feed_links(child, base_url, found);
+        }
+    }
+}
+
+fn parse_rss(channel: &XmlNode, item_parent: &XmlNode, feed_url: &str) -> Feed {
+    let entries = item_parent
+        .child_elements("item")
+        .map(|item| FeedEntry {
+            title: item.child_text("title"),
+            link: rss_link(item)
+                .or_else(|| {
+                    item.child_text("guid")
+                        .filter(|guid| guid.starts_with("http"))
+                })
+                .and_then(|link| resolve_href(&link, feed_url)),
+            published: item
+                .child_text("pubdate")
+                .or_else(|| item.child_text("date"))
+                .or_else(|| item.child_text("published")),
+            author: item
+                .child_text("creator")
+                .or_else(|| item.child_text("author")),
+            summary: item
+                .child_text("description")
+                .or_else(|| item.child_text("encoded"))
+                .map(|html| html_to_markdown(&html))
+                .filter(|summary| !summary.is_empty()),
+        })
+        .collect();
+
+    Feed {
+        feed_url: feed_url.to_string(),
+        title: channel.child_text("title"),
+        link: rss_link(channel).and_then(|link| resolve_href(&link, feed_url)),
+        entries,
+    }
+}
+
+fn parse_atom(feed: &XmlNode, feed_url: &str) -> Feed {
+    let entries = feed
+        .child_elements("entry")
+        .map(|entry| FeedEntry {
+            title: entry.child_text("title"),
+            link: atom_link(entry).and_then(|link| resolve_href(&link, feed_url)),
+            published: entry
+                .child_text("published")
+                .or_else(|| entry.child_text("updated")),
+            author: entry
+                .child_element("author")
+                .and_then(|author| author.child_text("name")),
+            summary: entry
+                .child_text("summary")
+                .or_else(|| entry.child_text("content"))
+                .map(|html| html_to_markdown(&html))
+                .filter(|summary| !summary.is_empty()),
+        })
+        .collect();
+
+    Feed {
+        feed_url: feed_url.to_string(),
+        title: feed.child_text("title"),
+        link: atom_link(feed).and_then(|link| resolve_href(&link, feed_url)),
+        entries,
+    }
+}
+
+// RSS 的 <link> 是文本内容；跳过 <atom:link rel="self"/> 等带命名空间前缀的空元素
+fn rss_link(node: &XmlNode) -> Option<String> {
+    node.children()
+        .iter()
+        .filter(|child| match child {
+            XmlNode::Element { name, .. } => name.eq_ignore_ascii_case("link"),
+            _ => false,
+        })
+        .map(|child| child.text().trim().to_string())
+        .find(|text| !text.is_empty())
+}
+
+fn atom_link(node: &XmlNode) -> Option<String> {
+    let links: Vec<&XmlNode> = node.child_elements("link").collect();
+    links
+        .iter()
+        .find(|link| link.attr("rel").is_none_or(|rel| rel == "alternate"))
+        .or_else(|| links.first())
+        .and_then(|link| link.attr("href"))
+        .map(|href| href.to_string())
+}
@@ -0,0 +1,210 @@
+use std::{error::Error as _, fmt, time::Duration};
+
+use fantoccini::error::{CmdError, ErrorStatus, NewSessionError};
+use rmcp::schemars;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FetchErrorKind {
+    InvalidUrl,
+    Dns,
+    Connect,
+    Tls,
+    Timeout,
+    HttpStatus,
+    TooLarge,
+    UnsupportedContentType,
+    Parse,
+    Browser,
+    BlockedByPolicy,
+    SectionNotFound,
+    // 返回内容超过总词数限制，按输入顺序丢弃；不对应具体的抓取错误
+    LimitExceeded,
+}
+
+#[derive(Debug, Clone)]
+pub enum FetchError {
+    InvalidUrl(String),
+    Dns(String),
+    Connect(String),
+    Tls(String),
+    Timeout(String),
+    HttpStatus {
+        status: u16,
+        retry_after: Option<Duration>,
+    },
+    TooLarge {
+        limit: u64,
+    },
+    UnsupportedContentType(String),
+    Parse(String),
+    Browser {
+        message: String,
+        transient: bool,
+    },
+    BlockedByPolicy(String),
+    SectionNotFound(String),
+}
+
+impl FetchError {
+    pub fn kind(&self) -> FetchErrorKind {
+        match self {
+            FetchError::InvalidUrl(_) => FetchErrorKind::InvalidUrl,
+            FetchError::Dns(_) => FetchErrorKind::Dns,
+            FetchError::Connect(_) => FetchErrorKind::Connect,
+            FetchError::Tls(_) => FetchErrorKind::Tls,
+            FetchError::Timeout(_) => FetchErrorKind::Timeout,
+            FetchError::HttpStatus { .. } => FetchErrorKind::HttpStatus,
+            FetchError::TooLarge { .. } => FetchErrorKind::TooLarge,
+            FetchError::UnsupportedContentType(_) => FetchErrorKind::UnsupportedContentType,
+            FetchError::Parse(_) => FetchErrorKind::Parse,
+            FetchError::Browser { .. } => FetchErrorKind::Browser,
+            FetchError::BlockedByPolicy(_) => FetchErrorKind::BlockedByPolicy,
+            FetchError::SectionNotFound(_) => FetchErrorKind::SectionNotFound,
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            FetchError::Dns(_) | FetchError::Connect(_) | FetchError::Timeout(_) => true,
+            FetchError::HttpStatus { status, .. } => is_retryable_status(*status),
+            FetchError::Browser { transient, .. } => *transient,
+            FetchError::InvalidUrl(_)
+            | FetchError::Tls(_)
+            | FetchError::TooLarge { .. }
+            | FetchError::UnsupportedContentType(_)
+            | FetchError::Parse(_)
+            | FetchError::BlockedByPolicy(_)
+            | FetchError::SectionNotFound(_) => false,
+        }
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            FetchError::HttpStatus { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    pub fn browser(message: impl Into<String>) -> Self {
+        FetchError::Browser {
+            message: message.into(),
+            transient: false,
+        }
+    }
+
+    pub fn from_reqwest(context: &str, err: &reqwest::Error) -> Self {
+        let message = format!("{context}: {}", error_chain(err));
+        if err.is_timeout() {
+            return FetchError::Timeout(message);
+        }
+        if err.is_builder() {
+            return FetchError::InvalidUrl(message);
+        }
+
+        let lower = message.to_ascii_lowercase();
+        if lower.contains("dns error")
+            || lower.contains("failed to lookup address")
+            || lower.contains("name or service not known")
+            || lower.contains("no such host")
+        {
+            FetchError::Dns(message)
+        } else if lower.contains("certificate")
+            || lower.contains("tls")
+            || lower.contains("ssl")
+            || lower.contains("handshake")
+        {
+            FetchError::Tls(message)
+        } else {
+            FetchError::Connect(message)
+        }
+    }
+
+    pub fn from_new_session(err: &NewSessionError) -> Self {
+        let message = format!("connect selenium failed: {err}");
+        let transient = match err {
+            NewSessionError::Failed(_)
+            | NewSessionError::FailedC(_)
+            | NewSessionError::Lost(_)
+            | NewSessionError::SessionNotCreated(_) => true,
+            NewSessionError::BadWebdriverUrl(_)
+            | NewSessionError::NotW3C(_)
+            | NewSessionError::UnexpectedError(_) => false,
+        };
+        FetchError::Browser { message, transient }
+    }
+
+    pub fn from_cmd(context: &str, err: &CmdError) -> Self {
+        let message = format!("{context}: {err}");
+        match err {
+            CmdError::Standard(wd) => {
+                // Firefox 导航失败时会返回 about:neterror 页面，可据此细分错误类型
+                let detail = wd.message.as_ref();
+                if detail.contains("dnsNotFound") {
+                    FetchError::Dns(message)
+                } else if detail.contains("nssFailure") || detail.contains("certerror") {
+                    FetchError::Tls(message)
+                } else if detail.contains("netTimeout") {
+                    FetchError::Timeout(message)
+                } else if detail.contains("connectionFailure") || detail.contains("netReset") {
+                    FetchError::Connect(message)
+                } else {
+                    let transient = matches!(
+                        wd.error,
+                        ErrorStatus::SessionNotCreated
+                            | ErrorStatus::InvalidSessionId
+                            | ErrorStatus::Timeout
+                            | ErrorStatus::ScriptTimeout
+                    );
+                    FetchError::Browser { message, transient }
+                }
+            }
+            CmdError::Failed(_) | CmdError::FailedC(_) | CmdError::Lost(_) => FetchError::Browser {
+                message,
+                transient: true,
+            },
+            CmdError::BadUrl(_) => FetchError::InvalidUrl(message),
+            CmdError::WaitTimeout => FetchError::Timeout(message),
+            _ => FetchError::browser(message),
+        }
+    }
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::InvalidUrl(msg)
+            | FetchError::Dns(msg)
+            | FetchError::Connect(msg)
+            | FetchError::Tls(msg)
+            | FetchError::Timeout(msg)
+            | FetchError::Parse(msg)
+            | FetchError::BlockedByPolicy(msg) => write!(f, "{msg}"),
+            FetchError::HttpStatus { status, .. } => write!(f, "non-success status: {status}"),
+            FetchError::TooLarge { limit } => write!(f, "response exceeded {limit} bytes"),
+            FetchError::UnsupportedContentType(content_type) => {
+                write!(f, "unsupported content type: {content_type}")
+            }
+            FetchError::Browser { message, .. } => write!(f, "{message}"),
+            FetchError::SectionNotFound(detail) => write!(f, "section not found: {detail}"),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+fn is_retryable_status(status: u16) -> bool {
+    matches!(status, 408 | 425 | 429 | 500 | 502 | 503 | 504)
+}
+
+fn error_chain(err: &reqwest::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(inner) = source {
+        message.push_str(": ");
+        message.push_str(&inner.to_string());
+        source = inner.source();
+    }
+    message
+}
@@ -1,11 +1,150 @@
-use std::time::Duration;
+use std::{env, time::Duration};
 
-use fantoccini::ClientBuilder;
+use fantoccini::{ClientBuilder, cookies::Cookie, wd::WebDriverCompatibleCommand};
 use futures::future::join_all;
-use reqwest::{Client, Proxy};
-use serde_json::{Map, json};
+use reqwest::{
+    Client, Method, Proxy,
+    header::{ACCEPT_LANGUAGE, CONTENT_TYPE, COOKIE, USER_AGENT},
+};
+use serde_json::{Map, Value, json};
 use url::Url;
 
+use crate::{
+    browser_action::{self, ActionOutcome, BrowserAction, InfiniteScroll, ScrollOutcome},
+    capture,
+    content_type::{self, ContentKind},
+    fetch_error::FetchError,
+    header_policy,
+    retry::{self, RetryPolicy},
+};
+
+const DEFAULT_USER_AGENT: &str =
+    "Mozilla/5.0 (X11; Linux x86_64; rv:135.0) Gecko/20100101 Firefox/135.0";
+const DEFAULT_MAX_BODY_BYTES: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct FetchConfig {
+    pub selenium_url: String,
+    pub proxy_url: Option<String>,
+    pub retry_policy: RetryPolicy,
+    pub max_body_bytes: u64,
+}
+
+impl FetchConfig {
+    pub fn from_env(selenium_url: String) -> Self {
+        let proxy_url = env::var("PROXY_URL")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        let max_body_bytes = env::var("MAX_RESPONSE_BYTES")
+            .ok()
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_MAX_BODY_BYTES);
+
+        Self {
+            selenium_url,
+            proxy_url,
+            retry_policy: RetryPolicy::from_env(),
+            max_body_bytes,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FetchedResponse {
+    pub content_type: Option<String>,
+    pub kind: ContentKind,
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FetchOptions {
+    pub headers: Vec<(String, String)>,
+    pub user_agent: Option<String>,
+    pub cookies: Vec<(String, String)>,
+    // 页面加载完成后、读取源码前在浏览器中执行的动作；非空时跳过直接请求
+    pub actions: Vec<BrowserAction>,
+    // 执行完动作后持续滚动加载更多内容；设置时同样跳过直接请求
+    pub infinite_scroll: Option<InfiniteScroll>,
+    // 浏览器渲染时自动点击 Cookie 同意弹窗的接受按钮
+    pub dismiss_consent: bool,
+    // 浏览器渲染时把同源 iframe 的内容内联到页面中
+    pub inline_iframes: bool,
+    // 浏览器渲染时按计算样式标记不可见元素，转换时一并移除
+    pub computed_visibility: bool,
+}
+
+impl FetchOptions {
+    pub fn needs_browser(&self) -> bool {
+        !self.actions.is_empty() || self.infinite_scroll.is_some()
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    // cookies 参数与 Cookie 请求头合并
+    fn cookie_pairs(&self) -> Vec<(String, String)> {
+        let mut pairs = self.cookies.clone();
+        if let Some(raw) = self.header(COOKIE.as_str()) {
+            for part in raw.split(';') {
+                if let Some((name, value)) = part.split_once('=') {
+                    let name = name.trim();
+                    if !name.is_empty() {
+                        pairs.push((name.to_string(), value.trim().to_string()));
+                    }
+                }
+            }
+        }
+        pairs
+    }
+
+    fn cookie_header(&self) -> Option<String> {
+        let pairs = self.cookie_pairs();
+        if pairs.is_empty() {
+            return None;
+        }
+        Some(
+            pairs
+                .iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect::<Vec<_>>()
+                .join("; "),
+        )
+    }
+
+    // 除 User-Agent / Accept-Language / Cookie 外需要通过 CDP 注入浏览器的请求头。
+    // CDP 注入的请求头会随页面内所有子资源发往第三方域名，因此 Authorization 等凭据请求头不注入，
+    // 带有这些请求头的抓取不会回退到浏览器（见 browser_unsendable_headers）
+    fn extra_browser_headers(&self) -> Map<String, Value> {
+        self.headers
+            .iter()
+            .filter(|(name, _)| {
+                ![USER_AGENT, ACCEPT_LANGUAGE, COOKIE]
+                    .iter()
+                    .any(|skip| name.eq_ignore_ascii_case(skip.as_str()))
+            })
+            .filter(|(name, _)| !header_policy::is_sensitive(name))
+            .map(|(name, value)| (name.clone(), Value::String(value.clone())))
+            .collect()
+    }
+
+    // 浏览器模式无法携带的凭据请求头（Cookie 通过 add_cookie 写入，不在此列）
+    fn browser_unsendable_headers(&self) -> Vec<&str> {
+        self.headers
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .filter(|name| {
+                header_policy::is_sensitive(name) && !name.eq_ignore_ascii_case(COOKIE.as_str())
+            })
+            .collect()
+    }
+}
+
 pub fn is_html_complete(html: &str) -> bool {
     const MIN_HTML_LEN: usize = 500;
     const SPA_ROOT_MAX_BODY_LEN: usize = 1000;
@@ -76,22 +215,48 @@ pub fn is_html_complete(html: &str) -> bool {
         "react", "vue", "angular", "next", "nuxt", "webpack",
     ];
     for framework in &framework_scripts {
-        if lower.contains(&format!("<script")) && lower.contains(framework) {
-            if body_content_len < SPA_SCRIPT_MAX_BODY_LEN {
-                return false;
-            }
+        if lower.contains("<script")
+            && lower.contains(framework)
+            && body_content_len < SPA_SCRIPT_MAX_BODY_LEN
+        {
+            return false;
         }
     }
 
     true
 }
 
-pub async fn simple_fetch_html(url: &str, proxy_url: Option<&str>) -> Result<String, String> {
+pub async fn simple_fetch(
+    url: &str,
+    config: &FetchConfig,
+    options: &FetchOptions,
+) -> Result<FetchedResponse, FetchError> {
+    send_request(url, config, options, true).await
+}
+
+// 不区分内容类型地下载原始字节（例如 .xml.gz 站点地图），沿用代理、大小限制与重试策略
+pub async fn fetch_bytes(
+    url: &str,
+    config: &FetchConfig,
+    options: &FetchOptions,
+) -> Result<FetchedResponse, FetchError> {
+    config
+        .retry_policy
+        .run(url, |_| send_request(url, config, options, false))
+        .await
+}
+
+async fn send_request(
+    url: &str,
+    config: &FetchConfig,
+    options: &FetchOptions,
+    reject_binary: bool,
+) -> Result<FetchedResponse, FetchError> {
     const SIMPLE_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
 
     let mut builder = Client::builder().timeout(SIMPLE_FETCH_TIMEOUT);
 
-    if let Some(proxy) = proxy_url {
+    if let Some(proxy) = config.proxy_url.as_deref() {
         let proxy = proxy.trim();
         if !proxy.is_empty() {
             let proxy_with_scheme = if proxy.contains("://") {
@@ -101,47 +266,281 @@ pub async fn simple_fetch_html(url: &str, proxy_url: Option<&str>) -> Result<Str
             };
 
             let reqwest_proxy = Proxy::all(&proxy_with_scheme)
-                .map_err(|e| format!("build proxy failed: {e}"))?;
+                .map_err(|e| FetchError::from_reqwest("build proxy failed", &e))?;
             builder = builder.proxy(reqwest_proxy);
         }
     }
 
     let client = builder
         .build()
-        .map_err(|e| format!("build http client failed: {e}"))?;
+        .map_err(|e| FetchError::from_reqwest("build http client failed", &e))?;
+
+    let user_agent = options
+        .user_agent
+        .as_deref()
+        .or_else(|| options.header(USER_AGENT.as_str()))
+        .unwrap_or(DEFAULT_USER_AGENT);
+
+    let mut request = client.get(url).header(USER_AGENT, user_agent);
+    for (name, value) in &options.headers {
+        if name.eq_ignore_ascii_case(USER_AGENT.as_str())
+            || name.eq_ignore_ascii_case(COOKIE.as_str())
+        {
+            continue;
+        }
+        request = request.header(name.as_str(), value.as_str());
+    }
+    if let Some(cookie) = options.cookie_header() {
+        request = request.header(COOKIE, cookie);
+    }
 
-    let resp = client
-        .get(url)
+    let mut resp = request
         .send()
         .await
-        .map_err(|e| format!("simple fetch failed: {e}"))?;
+        .map_err(|e| FetchError::from_reqwest("simple fetch failed", &e))?;
 
     if !resp.status().is_success() {
-        return Err(format!("simple fetch non-success status: {}", resp.status()));
+        return Err(FetchError::HttpStatus {
+            status: resp.status().as_u16(),
+            retry_after: retry::parse_retry_after(resp.headers()),
+        });
+    }
+
+    let limit = config.max_body_bytes;
+    if resp.content_length().is_some_and(|len| len > limit) {
+        return Err(FetchError::TooLarge { limit });
+    }
+
+    let content_type = resp
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+
+    // 响应头已声明为二进制类型时不再下载响应体
+    if reject_binary
+        && content_type.as_deref().and_then(content_type::from_header) == Some(ContentKind::Binary)
+    {
+        return Err(FetchError::UnsupportedContentType(
+            content_type::mime_essence(content_type.as_deref().unwrap_or_default()),
+        ));
+    }
+
+    // 未声明类型时按响应体开头嗅探，确认是二进制内容后立即停止下载
+    const SNIFF_LEN: usize = 1024;
+    let mut sniffed = !reject_binary;
+    let mut body = Vec::new();
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .map_err(|e| FetchError::from_reqwest("read simple fetch body failed", &e))?
+    {
+        if body.len() as u64 + chunk.len() as u64 > limit {
+            return Err(FetchError::TooLarge { limit });
+        }
+        body.extend_from_slice(&chunk);
+        if !sniffed && body.len() >= SNIFF_LEN {
+            sniffed = true;
+            if content_type::detect(content_type.as_deref(), &body) == ContentKind::Binary {
+                return Err(FetchError::UnsupportedContentType(
+                    content_type
+                        .as_deref()
+                        .map(content_type::mime_essence)
+                        .unwrap_or_else(|| "binary (sniffed)".to_string()),
+                ));
+            }
+        }
+    }
+
+    let kind = content_type::detect(content_type.as_deref(), &body);
+    Ok(FetchedResponse {
+        content_type,
+        kind,
+        body,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub enum FetchedDocument {
+    Html(String),
+    Pdf(Vec<u8>),
+    PlainText(String),
+    Markdown(String),
+    Json(String),
+    Xml(String),
+}
+
+impl FetchedDocument {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            FetchedDocument::Html(_) => "text/html",
+            FetchedDocument::Pdf(_) => "application/pdf",
+            FetchedDocument::PlainText(_) => "text/plain",
+            FetchedDocument::Markdown(_) => "text/markdown",
+            FetchedDocument::Json(_) => "application/json",
+            FetchedDocument::Xml(_) => "application/xml",
+        }
+    }
+}
+
+// 只允许 http/https：其他协议（如 file://）在回退到浏览器时可能读取 Selenium 主机上的本地文件
+fn ensure_http_url(url: &str) -> Result<(), FetchError> {
+    match Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(()),
+        _ => Err(FetchError::InvalidUrl("invalid url".to_string())),
     }
+}
 
-    resp.text()
+pub async fn fetch_document(
+    config: &FetchConfig,
+    url: &str,
+    options: &FetchOptions,
+) -> Result<FetchedDocument, FetchError> {
+    ensure_http_url(url)?;
+    config
+        .retry_policy
+        .run(url, |last_attempt| {
+            fetch_document_once(config, url, options, last_attempt)
+        })
         .await
-        .map_err(|e| format!("read simple fetch body failed: {e}"))
 }
 
-pub async fn fetch_html(
-    selenium_url: &str,
+async fn fetch_document_once(
+    config: &FetchConfig,
     url: &str,
-    proxy_url: Option<&str>,
-) -> Result<String, String> {
-    if let Ok(html) = simple_fetch_html(url, proxy_url).await {
-        if is_html_complete(&html) {
-            return Ok(html);
+    options: &FetchOptions,
+    last_attempt: bool,
+) -> Result<FetchedDocument, FetchError> {
+    if options.needs_browser() {
+        return browser_fetch_html(config, url, options)
+            .await
+            .map(FetchedDocument::Html);
+    }
+
+    match simple_fetch(url, config, options).await {
+        Ok(resp) => match resp.kind {
+            ContentKind::Pdf => return Ok(FetchedDocument::Pdf(resp.body)),
+            ContentKind::Binary => {
+                return Err(FetchError::UnsupportedContentType(
+                    resp.content_type
+                        .as_deref()
+                        .map(content_type::mime_essence)
+                        .unwrap_or_else(|| "binary (sniffed)".to_string()),
+                ));
+            }
+            ContentKind::Html => {
+                let html = content_type::decode_text(&resp.body, resp.content_type.as_deref());
+                // 凭据请求头无法带入浏览器，回退只会拿到未登录的页面，保留直接请求的结果
+                if is_html_complete(&html) || !options.browser_unsendable_headers().is_empty() {
+                    return Ok(FetchedDocument::Html(html));
+                }
+            }
+            ContentKind::PlainText
+            | ContentKind::Markdown
+            | ContentKind::Json
+            | ContentKind::Xml => {
+                let text = content_type::decode_text(&resp.body, resp.content_type.as_deref());
+                return Ok(match resp.kind {
+                    ContentKind::Json => FetchedDocument::Json(text),
+                    ContentKind::Xml => FetchedDocument::Xml(text),
+                    // raw.githubusercontent.com 等站点以 text/plain 返回 .md 文件
+                    ContentKind::Markdown => FetchedDocument::Markdown(text),
+                    _ if is_markdown_path(url) => FetchedDocument::Markdown(text),
+                    _ => FetchedDocument::PlainText(text),
+                });
+            }
+        },
+        // 域名无法解析、响应过大或非文本内容时浏览器同样无能为力，直接返回
+        Err(
+            e @ (FetchError::Dns(_)
+            | FetchError::TooLarge { .. }
+            | FetchError::UnsupportedContentType(_)),
+        ) => return Err(e),
+        // 限流/服务暂不可用时优先重试，最后一次尝试才回退到浏览器
+        Err(e @ FetchError::HttpStatus { .. }) if e.is_retryable() && !last_attempt => {
+            return Err(e);
         }
+        Err(e) if !options.browser_unsendable_headers().is_empty() => return Err(e),
+        Err(_) => {}
     }
 
+    browser_fetch_html(config, url, options)
+        .await
+        .map(FetchedDocument::Html)
+}
+
+fn is_markdown_path(url: &str) -> bool {
+    Url::parse(url)
+        .map(|parsed| {
+            let path = parsed.path().to_ascii_lowercase();
+            path.ends_with(".md") || path.ends_with(".markdown")
+        })
+        .unwrap_or(false)
+}
+
+#[derive(Debug, Clone)]
+pub struct RenderedPage {
+    pub html: String,
+    pub actions: Vec<ActionOutcome>,
+    pub scroll: Option<ScrollOutcome>,
+}
+
+// 始终通过浏览器渲染，并返回每个动作的执行结果
+pub async fn fetch_rendered(
+    config: &FetchConfig,
+    url: &str,
+    options: &FetchOptions,
+) -> Result<RenderedPage, FetchError> {
+    ensure_http_url(url)?;
+    config
+        .retry_policy
+        .run(url, |_| browser_fetch_page(config, url, options))
+        .await
+}
+
+pub async fn fetch_rendered_batch(
+    config: &FetchConfig,
+    urls: &[String],
+    options: &FetchOptions,
+) -> Vec<Result<RenderedPage, FetchError>> {
+    let futures: Vec<_> = urls
+        .iter()
+        .map(|url| fetch_rendered(config, url, options))
+        .collect();
+
+    join_all(futures).await
+}
+
+pub async fn browser_fetch_html(
+    config: &FetchConfig,
+    url: &str,
+    options: &FetchOptions,
+) -> Result<String, FetchError> {
+    ensure_http_url(url)?;
+    browser_fetch_page(config, url, options)
+        .await
+        .map(|page| page.html)
+}
+
+async fn browser_fetch_page(
+    config: &FetchConfig,
+    url: &str,
+    options: &FetchOptions,
+) -> Result<RenderedPage, FetchError> {
     const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
     const RENDER_WAIT_MS: u64 = 2000;
     const DOM_STABLE_CHECK_MS: u64 = 500;
 
+    let unsendable = options.browser_unsendable_headers();
+    if !unsendable.is_empty() {
+        return Err(FetchError::BlockedByPolicy(format!(
+            "headers cannot be sent in browser mode: {}",
+            unsendable.join(", ")
+        )));
+    }
+
     let mut caps = Map::new();
-    if let Some(proxy) = proxy_url {
+    if let Some(proxy) = config.proxy_url.as_deref() {
         caps.insert(
             "proxy".to_string(),
             json!({
@@ -152,50 +551,190 @@ pub async fn fetch_html(
         );
     }
 
+    insert_browser_identity_caps(&mut caps, options);
+
     let mut builder = ClientBuilder::native();
     if !caps.is_empty() {
         builder.capabilities(caps);
     }
 
     let client = builder
-        .connect(selenium_url)
+        .connect(&config.selenium_url)
         .await
-        .map_err(|e| format!("connect selenium failed: {e}"))?;
+        .map_err(|e| FetchError::from_new_session(&e))?;
+
+    let timeout = FETCH_TIMEOUT
+        + options
+            .actions
+            .iter()
+            .map(BrowserAction::budget)
+            .sum::<Duration>()
+        + options
+            .infinite_scroll
+            .as_ref()
+            .map(InfiniteScroll::budget)
+            .unwrap_or_default();
+
+    let result = match tokio::time::timeout(timeout, async {
+        set_extra_browser_headers(&client, options).await;
 
-    let result = match tokio::time::timeout(FETCH_TIMEOUT, async {
         client
             .goto(url)
             .await
-            .map_err(|e| format!("navigate failed: {e}"))?;
+            .map_err(|e| FetchError::from_cmd("navigate failed", &e))?;
+
+        // Cookie 只能在访问目标域名后写入，写入后重新加载页面
+        let cookies = options.cookie_pairs();
+        if !cookies.is_empty() {
+            for (name, value) in cookies {
+                client
+                    .add_cookie(Cookie::new(name, value))
+                    .await
+                    .map_err(|e| FetchError::from_cmd("add cookie failed", &e))?;
+            }
+            client
+                .goto(url)
+                .await
+                .map_err(|e| FetchError::from_cmd("navigate failed", &e))?;
+        }
 
         wait_for_ready_state_complete(&client).await?;
 
         tokio::time::sleep(Duration::from_millis(RENDER_WAIT_MS)).await;
 
-        let initial_dom_size = get_dom_node_count(&client).await?;
+        if options.dismiss_consent
+            && let Some(cmp) = browser_action::dismiss_consent(&client).await
+        {
+            tracing::debug!("dismissed {cmp} consent dialog on {url}");
+        }
+
+        // 短时间内仍有元素插入时说明页面还在渲染，再多等一会
+        let initial_node_count = get_dom_node_count(&client).await?;
         tokio::time::sleep(Duration::from_millis(DOM_STABLE_CHECK_MS)).await;
-        let final_dom_size = get_dom_node_count(&client).await?;
+        let final_node_count = get_dom_node_count(&client).await?;
 
-        if final_dom_size > initial_dom_size + 50 {
+        if final_node_count > initial_node_count + 10 {
             tokio::time::sleep(Duration::from_millis(1000)).await;
         }
 
-        client
-            .source()
-            .await
-            .map_err(|e| format!("read html failed: {e}"))
+        let actions = browser_action::run_actions(&client, &options.actions).await;
+
+        let scroll = match &options.infinite_scroll {
+            Some(scroll_options) => {
+                match browser_action::infinite_scroll(&client, scroll_options).await {
+                    Ok(outcome) => Some(outcome),
+                    Err(e) => {
+                        tracing::debug!("infinite scroll on {url} stopped early: {e}");
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+
+        if options.computed_visibility {
+            capture::tag_hidden_elements(&client).await;
+        }
+        let html = capture::capture_html(&client, options.inline_iframes).await?;
+
+        if html.len() as u64 > config.max_body_bytes {
+            return Err(FetchError::TooLarge {
+                limit: config.max_body_bytes,
+            });
+        }
+        Ok(RenderedPage {
+            html,
+            actions,
+            scroll,
+        })
     })
     .await
     {
         Ok(inner) => inner,
-        Err(_) => Err("fetch html timeout after 30s".to_string()),
+        Err(_) => Err(FetchError::Timeout(format!(
+            "fetch html timeout after {}s",
+            timeout.as_secs()
+        ))),
     };
 
     let _ = client.close().await;
     result
 }
 
-async fn wait_for_ready_state_complete(client: &fantoccini::Client) -> Result<(), String> {
+fn insert_browser_identity_caps(caps: &mut Map<String, Value>, options: &FetchOptions) {
+    let user_agent = options
+        .user_agent
+        .as_deref()
+        .or_else(|| options.header(USER_AGENT.as_str()));
+    let accept_language = options.header(ACCEPT_LANGUAGE.as_str());
+
+    let mut prefs = Map::new();
+    let mut args = Vec::new();
+    if let Some(ua) = user_agent {
+        prefs.insert("general.useragent.override".to_string(), json!(ua));
+        args.push(format!("--user-agent={ua}"));
+    }
+    if let Some(lang) = accept_language {
+        prefs.insert("intl.accept_languages".to_string(), json!(lang));
+        args.push(format!("--lang={lang}"));
+    }
+
+    if !prefs.is_empty() {
+        caps.insert("moz:firefoxOptions".to_string(), json!({ "prefs": prefs }));
+        caps.insert("goog:chromeOptions".to_string(), json!({ "args": args }));
+    }
+}
+
+#[derive(Debug)]
+struct CdpCommand {
+    cmd: &'static str,
+    params: Value,
+}
+
+impl WebDriverCompatibleCommand for CdpCommand {
+    fn endpoint(
+        &self,
+        base_url: &url::Url,
+        session_id: Option<&str>,
+    ) -> Result<url::Url, url::ParseError> {
+        base_url.join(&format!(
+            "session/{}/goog/cdp/execute",
+            session_id.unwrap_or_default()
+        ))
+    }
+
+    fn method_and_body(&self, _request_url: &url::Url) -> (Method, Option<String>) {
+        let body = json!({ "cmd": self.cmd, "params": self.params });
+        (Method::POST, Some(body.to_string()))
+    }
+}
+
+// 仅 Chromium 内核支持 CDP，其他浏览器忽略失败，只保留 UA / 语言 / Cookie 的注入
+async fn set_extra_browser_headers(client: &fantoccini::Client, options: &FetchOptions) {
+    let headers = options.extra_browser_headers();
+    if headers.is_empty() {
+        return;
+    }
+
+    let enable = CdpCommand {
+        cmd: "Network.enable",
+        params: json!({}),
+    };
+    let set_headers = CdpCommand {
+        cmd: "Network.setExtraHTTPHeaders",
+        params: json!({ "headers": headers }),
+    };
+
+    if let Err(e) = client.issue_cmd(enable).await {
+        tracing::debug!("cdp not available, extra headers skipped in browser: {e}");
+        return;
+    }
+    if let Err(e) = client.issue_cmd(set_headers).await {
+        tracing::debug!("set extra headers via cdp failed: {e}");
+    }
+}
+
+async fn wait_for_ready_state_complete(client: &fantoccini::Client) -> Result<(), FetchError> {
     const READY_STATE_TIMEOUT: Duration = Duration::from_secs(10);
     const CHECK_INTERVAL: Duration = Duration::from_millis(100);
     const MAX_ATTEMPTS: u32 = 100;
@@ -203,15 +742,12 @@ async fn wait_for_ready_state_complete(client: &fantoccini::Client) -> Result<()
     for _ in 0..MAX_ATTEMPTS {
         let result = tokio::time::timeout(READY_STATE_TIMEOUT, async {
             for _ in 0..MAX_ATTEMPTS {
-                match client.execute("return document.readyState", Vec::new()).await {
-                    Ok(json_result) => {
-                        if let Some(state) = json_result.as_str() {
-                            if state == "complete" {
-                                return Ok(());
-                            }
-                        }
-                    }
-                    Err(_) => {}
+                if let Ok(json_result) = client
+                    .execute("return document.readyState", Vec::new())
+                    .await
+                    && json_result.as_str() == Some("complete")
+                {
+                    return Ok(());
                 }
                 tokio::time::sleep(CHECK_INTERVAL).await;
             }
@@ -221,42 +757,39 @@ async fn wait_for_ready_state_complete(client: &fantoccini::Client) -> Result<()
 
         match result {
             Ok(Ok(())) => return Ok(()),
-            Ok(Err(e)) => return Err(format!("wait for readyState failed: {e}")),
+            Ok(Err(e)) => {
+                return Err(FetchError::Timeout(format!(
+                    "wait for readyState failed: {e}"
+                )));
+            }
             Err(_) => {}
         }
     }
 
-    Err("readyState check timeout".to_string())
+    Err(FetchError::Timeout("readyState check timeout".to_string()))
 }
 
-async fn get_dom_node_count(client: &fantoccini::Client) -> Result<usize, String> {
+pub(crate) async fn get_dom_node_count(client: &fantoccini::Client) -> Result<usize, FetchError> {
     let result = client
-        .execute("return document.documentElement.outerHTML.length", Vec::new())
+        .execute("return document.querySelectorAll('*').length", Vec::new())
         .await
-        .map_err(|e| format!("get dom size failed: {e}"))?;
+        .map_err(|e| FetchError::from_cmd("get dom node count failed", &e))?;
 
     let count = result
         .as_u64()
-        .ok_or_else(|| "get dom size returned non-integer".to_string())?;
+        .ok_or_else(|| FetchError::browser("get dom node count returned non-integer"))?;
 
     Ok(count as usize)
 }
 
-pub async fn fetch_html_batch(
-    selenium_url: &str,
+pub async fn fetch_document_batch(
+    config: &FetchConfig,
     urls: &[String],
-    proxy_url: Option<&str>,
-) -> Vec<Result<String, String>> {
+    options: &FetchOptions,
+) -> Vec<Result<FetchedDocument, FetchError>> {
     let futures: Vec<_> = urls
         .iter()
-        .map(|url| async move {
-            match Url::parse(url) {
-                Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {
-                    fetch_html(selenium_url, url, proxy_url).await
-                }
-                _ => Err("invalid url".to_string()),
-            }
-        })
+        .map(|url| fetch_document(config, url, options))
         .collect();
 
     join_all(futures).await
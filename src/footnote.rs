@@ -0,0 +1,196 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    parser::{Attr, HtmlNode, attr, classes},
+    text,
+};
+
+// 脚注标记的最大长度（去掉方括号后），更长的页内链接视为普通链接
+const MAX_MARKER_LEN: usize = 8;
+// 脚注正文中指回引用处的链接容器：MediaWiki、Pandoc / markdown-it 等使用的 class
+const BACKLINK_CLASSES: [&str; 3] = ["mw-cite-backlink", "footnote-backref", "footnote-back"];
+// 脚注列表容器的 class / id 中包含的词：维基百科的 references / reflist、Pandoc 与 markdown-it 的 footnotes
+const CONTAINER_MARKERS: [&str; 4] = ["footnotes", "references", "reflist", "endnotes"];
+
+// 页面内的脚注引用与脚注正文的对应关系
+#[derive(Debug, Clone, Default)]
+pub struct Footnotes {
+    // 脚注正文元素的 id → GFM 脚注标签
+    labels: HashMap<String, String>,
+    // 按首次引用顺序排列的标签
+    order: Vec<String>,
+    // 引用处（<a> 及外层 <sup>）的 id，脚注正文中指向它们的链接是返回链接
+    backrefs: HashSet<String>,
+}
+
+impl Footnotes {
+    // 扫描页面：<a href="#id"> 指向列表项或 role="doc-footnote" 等脚注正文、且文字形如 [1] 时视为脚注引用
+    pub fn collect(nodes: &[HtmlNode]) -> Self {
+        let mut targets = HashSet::new();
+        collect_targets(nodes, false, &mut targets);
+
+        let mut footnotes = Self::default();
+        if !targets.is_empty() {
+            footnotes.collect_references(nodes, &targets, None);
+        }
+        footnotes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    // <a> 是脚注引用时返回其标签
+    pub fn reference_label(&self, attrs: &[Attr]) -> Option<&str> {
+        self.labels.get(fragment(attrs)?).map(String::as_str)
+    }
+
+    // 元素是被引用过的脚注正文时返回其标签
+    pub fn definition_label(&self, attrs: &[Attr]) -> Option<&str> {
+        self.labels.get(attr(attrs, "id")?).map(String::as_str)
+    }
+
+    // 脚注正文中的返回链接（↩、^ 等），转换时丢弃
+    pub fn is_backlink(&self, tag: &str, attrs: &[Attr]) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        attr(attrs, "role") == Some("doc-backlink")
+            || classes(attrs).any(|class| BACKLINK_CLASSES.contains(&class))
+            || (tag == "a" && fragment(attrs).is_some_and(|id| self.backrefs.contains(id)))
+    }
+
+    // 按引用顺序输出脚注定义，多行内容缩进 4 格
+    pub fn render_definitions(&self, definitions: &HashMap<String, String>) -> String {
+        let mut output = String::new();
+        for label in &self.order {
+            let Some(content) = definitions.get(label).filter(|content| !content.is_empty()) else {
+                continue;
+            };
+            let mut lines = content.lines();
+            output.push_str(&format!(
+                "[^{label}]: {}\n",
+                lines.next().unwrap_or_default()
+            ));
+            for line in lines {
+                if !line.trim().is_empty() {
+                    output.push_str("    ");
+                    output.push_str(line);
+                }
+                output.push('\n');
+            }
+        }
+        output
+    }
+
+    fn collect_references(
+        &mut self,
+        nodes: &[HtmlNode],
+        targets: &HashSet<String>,
+        sup_id: Option<&str>,
+    ) {
+        for node in nodes {
+            let HtmlNode::Element {
+                tag,
+                attrs,
+                children,
+            } = node
+            else {
+                continue;
+            };
+            if tag == "a"
+                && let Some(target) = fragment(attrs).filter(|id| targets.contains(*id))
+                && let Some(marker) = reference_marker(attrs, children, sup_id.is_some())
+            {
+                self.add_reference(target, &marker);
+                self.backrefs.extend(attr(attrs, "id").map(str::to_string));
+                self.backrefs.extend(sup_id.map(str::to_string));
+                continue;
+            }
+            let sup_id = if tag == "sup" {
+                attr(attrs, "id").or(sup_id)
+            } else {
+                sup_id
+            };
+            self.collect_references(children, targets, sup_id);
+        }
+    }
+
+    fn add_reference(&mut self, target: &str, marker: &str) {
+        if self.labels.contains_key(target) {
+            return;
+        }
+        // 优先沿用页面上的标记（1、a、note3），冲突或含特殊字符时按顺序编号
+        let taken = |label: &str| self.order.iter().any(|used| used == label);
+        let valid = !marker.is_empty()
+            && marker
+                .chars()
+                .all(|ch| ch.is_alphanumeric() || ch == '-' || ch == '_');
+        let label = if valid && !taken(marker) {
+            marker.to_string()
+        } else {
+            let mut number = self.order.len() + 1;
+            while taken(&number.to_string()) {
+                number += 1;
+            }
+            number.to_string()
+        };
+        self.labels.insert(target.to_string(), label.clone());
+        self.order.push(label);
+    }
+}
+
+// 可能是脚注正文的元素 id：role="doc-footnote" / "doc-endnote" 的元素，或位于脚注列表容器内、
+// 本身是列表项或 class 含 footnote 的元素；容器外的普通列表项不视为脚注正文
+fn collect_targets(nodes: &[HtmlNode], in_container: bool, targets: &mut HashSet<String>) {
+    for node in nodes {
+        let HtmlNode::Element {
+            tag,
+            attrs,
+            children,
+        } = node
+        else {
+            continue;
+        };
+        if let Some(id) = attr(attrs, "id")
+            && (matches!(attr(attrs, "role"), Some("doc-footnote" | "doc-endnote"))
+                || (in_container
+                    && (tag == "li" || classes(attrs).any(|class| class.contains("footnote")))))
+        {
+            targets.insert(id.to_string());
+        }
+        collect_targets(children, in_container || is_container(attrs), targets);
+    }
+}
+
+// 脚注列表容器：role="doc-endnotes"，或 class / id 含 footnotes、references 等词
+fn is_container(attrs: &[Attr]) -> bool {
+    attr(attrs, "role") == Some("doc-endnotes")
+        || classes(attrs).chain(attr(attrs, "id")).any(|name| {
+            let name = name.to_ascii_lowercase();
+            CONTAINER_MARKERS.iter().any(|marker| name.contains(marker))
+        })
+}
+
+// 引用标记：位于 <sup> 内、带 role="doc-noteref" / footnote-ref 类，或文字本身带方括号
+fn reference_marker(attrs: &[Attr], children: &[HtmlNode], in_sup: bool) -> Option<String> {
+    let text = text::inline_text(children);
+    let bracketed = text.starts_with('[') && text.ends_with(']');
+    let marked = attr(attrs, "role") == Some("doc-noteref")
+        || classes(attrs).any(|class| class.starts_with("footnote-ref"));
+    if !(in_sup || marked || bracketed) {
+        return None;
+    }
+    let marker = text
+        .trim_matches(|ch: char| ch.is_whitespace() || matches!(ch, '[' | ']' | '(' | ')'))
+        .to_string();
+    (!marker.is_empty() && marker.chars().count() <= MAX_MARKER_LEN).then_some(marker)
+}
+
+// 页内链接 href="#id" 的 id
+fn fragment(attrs: &[Attr]) -> Option<&str> {
+    attr(attrs, "href")?
+        .trim()
+        .strip_prefix('#')
+        .filter(|id| !id.is_empty())
+}
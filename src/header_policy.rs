@@ -0,0 +1,110 @@
+use std::env;
+
+use crate::{fetch_error::FetchError, fetcher::FetchOptions};
+
+// 携带凭据的请求头，默认禁止由调用方传入
+const SENSITIVE_HEADERS: [&str; 7] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "x-api-key",
+    "x-auth-token",
+    "x-csrf-token",
+    "x-xsrf-token",
+];
+
+// 由 HTTP 客户端自行管理的请求头，始终禁止覆盖
+const FORBIDDEN_HEADERS: [&str; 9] = [
+    "host",
+    "content-length",
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "te",
+    "trailer",
+    "upgrade",
+];
+
+// 携带凭据的请求头（名称不区分大小写）
+pub fn is_sensitive(name: &str) -> bool {
+    SENSITIVE_HEADERS.contains(&name.to_ascii_lowercase().as_str())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SensitiveHeaders {
+    Forbid,
+    Allow,
+    AllowList(Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+pub struct HeaderPolicy {
+    pub sensitive: SensitiveHeaders,
+}
+
+impl Default for HeaderPolicy {
+    fn default() -> Self {
+        Self {
+            sensitive: SensitiveHeaders::Forbid,
+        }
+    }
+}
+
+impl HeaderPolicy {
+    pub fn from_env() -> Self {
+        let raw = env::var("SENSITIVE_HEADERS")
+            .ok()
+            .map(|v| v.trim().to_ascii_lowercase())
+            .filter(|v| !v.is_empty());
+
+        let sensitive = match raw.as_deref() {
+            None | Some("forbid") => SensitiveHeaders::Forbid,
+            Some("allow") => SensitiveHeaders::Allow,
+            Some(list) => SensitiveHeaders::AllowList(
+                list.split(',')
+                    .map(|name| name.trim().to_string())
+                    .filter(|name| !name.is_empty())
+                    .collect(),
+            ),
+        };
+
+        Self { sensitive }
+    }
+
+    pub fn check(&self, options: &FetchOptions) -> Result<(), FetchError> {
+        for (name, _) in &options.headers {
+            self.check_header(&name.to_ascii_lowercase())?;
+        }
+        if !options.cookies.is_empty() {
+            self.check_header("cookie")?;
+        }
+        Ok(())
+    }
+
+    fn check_header(&self, name: &str) -> Result<(), FetchError> {
+        if FORBIDDEN_HEADERS.contains(&name) {
+            return Err(FetchError::BlockedByPolicy(format!(
+                "header not allowed: {name}"
+            )));
+        }
+
+        if !is_sensitive(name) {
+            return Ok(());
+        }
+
+        let allowed = match &self.sensitive {
+            SensitiveHeaders::Forbid => false,
+            SensitiveHeaders::Allow => true,
+            SensitiveHeaders::AllowList(list) => list.iter().any(|allowed| allowed == name),
+        };
+
+        if allowed {
+            Ok(())
+        } else {
+            Err(FetchError::BlockedByPolicy(format!(
+                "sensitive header not allowed by server policy: {name}"
+            )))
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use serde::de::IgnoredAny;
+
+const INDENT: &str = "  ";
+
+// 按 2 空格缩进重新排版 JSON 文本：逐字符处理而不经过 serde_json::Value，
+// 保持原始的键顺序、数字与转义写法；输入不是合法 JSON 时返回 None
+pub fn pretty_print(json: &str) -> Option<String> {
+    serde_json::from_str::<IgnoredAny>(json).ok()?;
+
+    let mut output = String::with_capacity(json.len() * 2);
+    let mut depth = 0usize;
+    let mut chars = json.trim().chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' => {
+                output.push(ch);
+                while let Some(ch) = chars.next() {
+                    output.push(ch);
+                    match ch {
+                        '\\' => output.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '{' | '[' => {
+                output.push(ch);
+                while chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+                let close = if ch == '{' { '}' } else { ']' };
+                // 空对象与空数组保持在同一行
+                if chars.next_if_eq(&close).is_some() {
+                    output.push(close);
+                    continue;
+                }
+                depth += 1;
+                new_line(&mut output, depth);
+            }
+            '}' | ']' => {
+                depth = depth.saturating_sub(1);
+                new_line(&mut output, depth);
+                output.push(ch);
+            }
+            ',' => {
+                output.push(ch);
+                new_line(&mut output, depth);
+            }
+            ':' => output.push_str(": "),
+            ch if ch.is_whitespace() => {}
+            ch => output.push(ch),
+        }
+    }
+    Some(output)
+}
+
+fn new_line(output: &mut String, depth: usize) {
+    output.push('\n');
+    for _ in 0..depth {
+        output.push_str(INDENT);
+    }
+}
@@ -1,26 +1,170 @@
+pub mod browser_action;
+pub mod capture;
+pub mod content_type;
 pub mod converter;
+pub mod crawl;
+pub mod dom_filter;
 pub mod entities;
+pub mod extract;
+pub mod feed;
+pub mod fetch_error;
 pub mod fetcher;
+pub mod footnote;
+pub mod header_policy;
 pub mod html_to_image;
+pub mod json;
 pub mod limit;
 pub mod markdown_to_image;
+pub mod math;
+pub mod pagination;
 pub mod parser;
+pub mod pdf;
+pub mod retry;
+pub mod section;
+pub mod selector;
 pub mod server;
+pub mod sitemap;
+pub mod table;
 pub mod text;
+pub mod toc;
 pub mod urls;
 pub mod word_count;
+pub mod xml;
+
+// HTML 转换共用的设置：选择器范围、章节、是否移除浮层以及 Markdown 输出选项
+#[derive(Debug, Clone)]
+pub struct HtmlConversion {
+    pub scope: selector::ContentScope,
+    // 只保留该标题下的章节，按锚点或标题文字匹配
+    pub section: Option<String>,
+    // 转换前移除 Cookie 同意弹窗与固定定位的浮层，默认开启
+    pub drop_overlays: bool,
+    pub markdown: converter::MarkdownOptions,
+}
+
+impl Default for HtmlConversion {
+    fn default() -> Self {
+        Self {
+            scope: selector::ContentScope::default(),
+            section: None,
+            drop_overlays: true,
+            markdown: converter::MarkdownOptions::default(),
+        }
+    }
+}
 
 pub fn html_to_markdown(html: &str) -> String {
-    let dom = parser::parse_html(html);
-    converter::convert_to_markdown(&dom)
+    let conversion = HtmlConversion::default();
+    let dom = prepared_dom(html, &conversion, true);
+    converter::convert_to_markdown_document(&dom, &conversion.markdown).markdown
+}
+
+// 以下 *_scoped / *_document 在 section 指定的标题不存在时返回 SectionNotFound
+pub fn html_to_markdown_document(
+    html: &str,
+    conversion: &HtmlConversion,
+) -> Result<converter::MarkdownDocument, fetch_error::FetchError> {
+    let dom = scoped_dom(html, conversion, true)?;
+    Ok(converter::convert_to_markdown_document(
+        &dom,
+        &conversion.markdown,
+    ))
 }
 
 pub fn html_to_text(html: &str) -> String {
-    let dom = parser::parse_html(html);
-    text::convert_to_text(&dom)
+    text::convert_to_text(&prepared_dom(html, &HtmlConversion::default(), true))
+}
+
+pub fn html_to_text_scoped(
+    html: &str,
+    conversion: &HtmlConversion,
+) -> Result<String, fetch_error::FetchError> {
+    let dom = scoped_dom(html, conversion, true)?;
+    Ok(text::convert_to_text(&dom))
 }
 
 pub fn html_to_urls_markdown(html: &str, base_url: &str) -> String {
-    let dom = parser::parse_html(html);
+    let dom = prepared_dom(html, &HtmlConversion::default(), false);
     urls::extract_urls(&dom, base_url)
 }
+
+pub fn html_to_urls_markdown_scoped(
+    html: &str,
+    base_url: &str,
+    conversion: &HtmlConversion,
+) -> Result<String, fetch_error::FetchError> {
+    let dom = scoped_dom(html, conversion, false)?;
+    Ok(urls::extract_urls(&dom, base_url))
+}
+
+pub fn html_to_tables(
+    html: &str,
+    conversion: &HtmlConversion,
+    with_csv: bool,
+) -> Result<Vec<table::Table>, fetch_error::FetchError> {
+    let dom = scoped_dom(html, conversion, true)?;
+    Ok(table::extract_tables(&dom, with_csv))
+}
+
+// 所有 HTML 转换共用的处理流程：解析 → 选择器 → 移除不可见节点与浮层（visible_only 时）→ 分配标题锚点。
+// 选择器作用于页面原始 DOM，:nth-child 等位置按原始结构计算，各 kind 选中的元素一致；
+// 链接列表保留隐藏元素中的链接，因此不做可见性过滤
+fn prepared_dom(
+    html: &str,
+    conversion: &HtmlConversion,
+    visible_only: bool,
+) -> Vec<parser::HtmlNode> {
+    let mut dom = conversion.scope.apply(parser::parse_html(html));
+    if visible_only {
+        dom = dom_filter::strip_hidden(dom);
+        if conversion.drop_overlays {
+            dom = dom_filter::strip_overlays(dom);
+        }
+    }
+    toc::assign_anchors(&mut dom);
+    dom
+}
+
+// 在 prepared_dom 之后按 section 截取章节，这是 HTML 转换唯一可能失败的步骤
+fn scoped_dom(
+    html: &str,
+    conversion: &HtmlConversion,
+    visible_only: bool,
+) -> Result<Vec<parser::HtmlNode>, fetch_error::FetchError> {
+    let dom = prepared_dom(html, conversion, visible_only);
+    match conversion.section.as_deref() {
+        Some(query) => {
+            section::extract_section(dom, query).map_err(fetch_error::FetchError::SectionNotFound)
+        }
+        None => Ok(dom),
+    }
+}
+
+pub fn text_to_urls_markdown(text: &str) -> String {
+    let mut seen = std::collections::HashSet::new();
+    text::find_urls(text)
+        .into_iter()
+        .filter(|url| seen.insert(url.clone()))
+        .map(|url| format!("![text link]({url})"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn json_to_markdown(json: &str) -> String {
+    converter::fenced_code(&pretty_json(json), "json")
+}
+
+pub fn pretty_json(json: &str) -> String {
+    json::pretty_print(json).unwrap_or_else(|| json.trim().to_string())
+}
+
+pub fn xml_to_markdown(xml: &str) -> String {
+    converter::fenced_code(&pretty_xml(xml), "xml")
+}
+
+pub fn pretty_xml(xml: &str) -> String {
+    match xml::parse_xml(xml) {
+        Ok(nodes) => xml::pretty_print(&nodes),
+        Err(_) => xml.trim().to_string(),
+    }
+}
@@ -0,0 +1,638 @@
+use crate::parser::{self, Attr, HtmlNode, attr, classes};
+
+// Unicode 符号与 LaTeX 命令的对应关系：MathML 转 LaTeX 时用左列查右列，线性化时反查
+const SYMBOLS: [(&str, &str); 92] = [
+    ("α", "\\alpha"),
+    ("β", "\\beta"),
+    ("γ", "\\gamma"),
+    ("δ", "\\delta"),
+    ("ε", "\\epsilon"),
+    ("ζ", "\\zeta"),
+    ("η", "\\eta"),
+    ("θ", "\\theta"),
+    ("ι", "\\iota"),
+    ("κ", "\\kappa"),
+    ("λ", "\\lambda"),
+    ("μ", "\\mu"),
+    ("ν", "\\nu"),
+    ("ξ", "\\xi"),
+    ("π", "\\pi"),
+    ("ρ", "\\rho"),
+    ("σ", "\\sigma"),
+    ("τ", "\\tau"),
+    ("υ", "\\upsilon"),
+    ("φ", "\\phi"),
+    ("χ", "\\chi"),
+    ("ψ", "\\psi"),
+    ("ω", "\\omega"),
+    ("Γ", "\\Gamma"),
+    ("Δ", "\\Delta"),
+    ("Θ", "\\Theta"),
+    ("Λ", "\\Lambda"),
+    ("Ξ", "\\Xi"),
+    ("Π", "\\Pi"),
+    ("Σ", "\\Sigma"),
+    ("Φ", "\\Phi"),
+    ("Ψ", "\\Psi"),
+    ("Ω", "\\Omega"),
+    ("∑", "\\sum"),
+    ("∏", "\\prod"),
+    ("∫", "\\int"),
+    ("∬", "\\iint"),
+    ("∮", "\\oint"),
+    ("∞", "\\infty"),
+    ("∂", "\\partial"),
+    ("∇", "\\nabla"),
+    ("±", "\\pm"),
+    ("∓", "\\mp"),
+    ("×", "\\times"),
+    ("÷", "\\div"),
+    ("·", "\\cdot"),
+    ("⋅", "\\cdot"),
+    ("∗", "\\ast"),
+    ("≤", "\\leq"),
+    ("≥", "\\geq"),
+    ("≠", "\\neq"),
+    ("≈", "\\approx"),
+    ("≡", "\\equiv"),
+    ("∼", "\\sim"),
+    ("≅", "\\cong"),
+    ("∝", "\\propto"),
+    ("≪", "\\ll"),
+    ("≫", "\\gg"),
+    ("→", "\\to"),
+    ("←", "\\leftarrow"),
+    ("↔", "\\leftrightarrow"),
+    ("⇒", "\\Rightarrow"),
+    ("⇐", "\\Leftarrow"),
+    ("⇔", "\\Leftrightarrow"),
+    ("↦", "\\mapsto"),
+    ("∈", "\\in"),
+    ("∉", "\\notin"),
+    ("∋", "\\ni"),
+    ("⊂", "\\subset"),
+    ("⊆", "\\subseteq"),
+    ("⊃", "\\supset"),
+    ("⊇", "\\supseteq"),
+    ("∪", "\\cup"),
+    ("∩", "\\cap"),
+    ("∅", "\\emptyset"),
+    ("∀", "\\forall"),
+    ("∃", "\\exists"),
+    ("¬", "\\neg"),
+    ("∧", "\\land"),
+    ("∨", "\\lor"),
+    ("…", "\\ldots"),
+    ("⋯", "\\cdots"),
+    ("′", "\\prime"),
+    ("∘", "\\circ"),
+    ("⊗", "\\otimes"),
+    ("⊕", "\\oplus"),
+    ("ℏ", "\\hbar"),
+    ("ℓ", "\\ell"),
+    ("⟨", "\\langle"),
+    ("⟩", "\\rangle"),
+    ("⌊", "\\lfloor"),
+    ("⌋", "\\rfloor"),
+];
+
+// 线性化时额外识别的同义命令
+const SYMBOL_ALIASES: [(&str, &str); 12] = [
+    ("\\le", "≤"),
+    ("\\ge", "≥"),
+    ("\\ne", "≠"),
+    ("\\lt", "<"),
+    ("\\gt", ">"),
+    ("\\rightarrow", "→"),
+    ("\\varepsilon", "ε"),
+    ("\\varphi", "φ"),
+    ("\\vartheta", "θ"),
+    ("\\dots", "…"),
+    ("\\ldotp", "."),
+    ("\\colon", ":"),
+];
+
+// 黑板粗体字母
+const BLACKBOARD: [(char, &str); 5] = [('ℝ', "R"), ('ℕ', "N"), ('ℤ', "Z"), ('ℚ', "Q"), ('ℂ', "C")];
+
+// 以正体输出的函数名
+const FUNCTIONS: [&str; 24] = [
+    "sin", "cos", "tan", "cot", "sec", "csc", "sinh", "cosh", "tanh", "arcsin", "arccos", "arctan",
+    "log", "ln", "exp", "lim", "max", "min", "sup", "inf", "det", "gcd", "deg", "arg",
+];
+
+// 只影响排版、线性化时忽略的命令
+const LAYOUT_COMMANDS: [&str; 16] = [
+    "left",
+    "right",
+    "big",
+    "Big",
+    "bigg",
+    "Bigg",
+    "bigl",
+    "bigr",
+    "Bigl",
+    "Bigr",
+    "displaystyle",
+    "textstyle",
+    "scriptstyle",
+    "limits",
+    "nolimits",
+    "nonumber",
+];
+
+// 取参数内容、忽略字体样式的命令
+const STYLE_COMMANDS: [&str; 13] = [
+    "text",
+    "textrm",
+    "textbf",
+    "textit",
+    "mbox",
+    "mathrm",
+    "mathbf",
+    "mathit",
+    "mathsf",
+    "mathtt",
+    "mathcal",
+    "boldsymbol",
+    "operatorname",
+];
+
+// 重音命令及线性化时附加的组合字符
+const ACCENTS: [(&str, char); 7] = [
+    ("hat", '\u{0302}'),
+    ("bar", '\u{0304}'),
+    ("overline", '\u{0305}'),
+    ("vec", '\u{20D7}'),
+    ("tilde", '\u{0303}'),
+    ("dot", '\u{0307}'),
+    ("ddot", '\u{0308}'),
+];
+
+// MathJax v2 在公式脚本旁插入的预览与渲染结果，源码以脚本为准
+const MATHJAX_OUTPUT_CLASSES: [&str; 9] = [
+    "MathJax_Preview",
+    "MathJax",
+    "MathJax_Display",
+    "MathJax_SVG",
+    "MathJax_SVG_Display",
+    "MathJax_CHTML",
+    "MathJax_PHTML",
+    "MathJax_MathML",
+    "MJX_Assistive_MathML",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula {
+    pub tex: String,
+    pub display: bool,
+}
+
+pub enum MathNode {
+    Formula(Formula),
+    // 与公式源码重复的渲染结果，整体丢弃
+    Rendered,
+}
+
+// 识别公式元素：MathJax v2 脚本、KaTeX、MathJax v3 容器、MathML 与维基百科的公式图片
+pub fn classify(tag: &str, attrs: &[Attr], children: &[HtmlNode]) -> Option<MathNode> {
+    let formula = |tex: String, display: bool| {
+        let tex = strip_displaystyle(tex.trim());
+        (!tex.is_empty()).then_some(MathNode::Formula(Formula { tex, display }))
+    };
+
+    match tag {
+        "script" => {
+            let kind = attr(attrs, "type")?.to_ascii_lowercase();
+            let source = text_content(children);
+            let display = kind.contains("mode=display");
+            if kind.starts_with("math/tex") {
+                formula(source, display)
+            } else if kind.starts_with("math/mml") {
+                let nodes = parser::parse_html(&source);
+                let (tex, block) = math_element_tex(&nodes)?;
+                formula(tex, display || block)
+            } else {
+                None
+            }
+        }
+        "math" => {
+            let display = attr(attrs, "display") == Some("block");
+            let tex = annotation_tex(children)
+                .or_else(|| attr(attrs, "alttext").map(str::to_string))
+                .unwrap_or_else(|| mathml_to_tex(children));
+            formula(tex, display)
+        }
+        "mjx-container" => {
+            let display = attr(attrs, "display") == Some("true");
+            match math_element_tex(children) {
+                Some((tex, _)) => formula(tex, display),
+                None => Some(MathNode::Rendered),
+            }
+        }
+        "img" if has_class_prefix(attrs, "mwe-math-fallback-image") => {
+            let display = has_class_prefix(attrs, "mwe-math-fallback-image-display");
+            formula(attr(attrs, "alt")?.to_string(), display)
+        }
+        _ if has_class(attrs, "katex-display") => formula(annotation_tex(children)?, true),
+        _ if has_class(attrs, "katex") => formula(annotation_tex(children)?, false),
+        _ if classes(attrs).any(|class| MATHJAX_OUTPUT_CLASSES.contains(&class)) => {
+            Some(MathNode::Rendered)
+        }
+        _ => None,
+    }
+}
+
+// Markdown 中的公式：行内 $...$，独立公式 $$...$$ 单独成段
+pub fn to_markdown(formula: &Formula, inline_only: bool) -> String {
+    if formula.display && !inline_only {
+        format!("\n\n$$\n{}\n$$\n\n", formula.tex)
+    } else {
+        format!("${}$", collapse_whitespace(&formula.tex))
+    }
+}
+
+fn has_class(attrs: &[Attr], name: &str) -> bool {
+    classes(attrs).any(|class| class == name)
+}
+
+fn has_class_prefix(attrs: &[Attr], prefix: &str) -> bool {
+    classes(attrs).any(|class| class.starts_with(prefix))
+}
+
+fn text_content(nodes: &[HtmlNode]) -> String {
+    let mut output = String::new();
+    for node in nodes {
+        match node {
+            HtmlNode::Text(text) => output.push_str(text),
+            HtmlNode::Element { children, .. } => output.push_str(&text_content(children)),
+        }
+    }
+    output
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// 维基百科等在 TeX 外层包裹的 {\displaystyle ...}
+fn strip_displaystyle(tex: &str) -> String {
+    tex.strip_prefix("{\\displaystyle")
+        .and_then(|rest| rest.strip_suffix('}'))
+        .map_or(tex, str::trim)
+        .to_string()
+}
+
+// 在子树中查找第一个 <math>，返回其 LaTeX 与是否为独立公式
+fn math_element_tex(nodes: &[HtmlNode]) -> Option<(String, bool)> {
+    nodes.iter().find_map(|node| match node {
+        HtmlNode::Element {
+            tag,
+            attrs,
+            children,
+        } if tag == "math" => {
+            let tex = annotation_tex(children)
+                .or_else(|| attr(attrs, "alttext").map(str::to_string))
+                .unwrap_or_else(|| mathml_to_tex(children));
+            Some((tex, attr(attrs, "display") == Some("block")))
+        }
+        HtmlNode::Element { children, .. } => math_element_tex(children),
+        HtmlNode::Text(_) => None,
+    })
+}
+
+// KaTeX 与 MathML <semantics> 中保存的 TeX 源码
+fn annotation_tex(nodes: &[HtmlNode]) -> Option<String> {
+    nodes.iter().find_map(|node| match node {
+        HtmlNode::Element {
+            tag,
+            attrs,
+            children,
+        } if tag == "annotation"
+            && attr(attrs, "encoding")
+                .is_some_and(|encoding| encoding.eq_ignore_ascii_case("application/x-tex")) =>
+        {
+            Some(text_content(children).trim().to_string())
+        }
+        HtmlNode::Element { children, .. } => annotation_tex(children),
+        HtmlNode::Text(_) => None,
+    })
+}
+
+// 把 Presentation MathML 转换为近似的 LaTeX
+pub fn mathml_to_tex(nodes: &[HtmlNode]) -> String {
+    let mut output = String::new();
+    for node in nodes {
+        push_tex(&mut output, &node_tex(node));
+    }
+    output.trim().to_string()
+}
+
+fn node_tex(node: &HtmlNode) -> String {
+    let (tag, attrs, children) = match node {
+        HtmlNode::Text(text) => return escape_tex(text.trim()),
+        HtmlNode::Element {
+            tag,
+            attrs,
+            children,
+        } => (tag.as_str(), attrs.as_slice(), children.as_slice()),
+    };
+    let args: Vec<String> = children
+        .iter()
+        .filter(|child| matches!(child, HtmlNode::Element { .. }))
+        .map(node_tex)
+        .collect();
+    let arg = |idx: usize| args.get(idx).cloned().unwrap_or_default();
+
+    match tag {
+        "annotation" | "annotation-xml" | "mphantom" | "none" | "mprescripts" => String::new(),
+        "semantics" => arg(0),
+        "mi" => identifier(text_content(children).trim()),
+        "mn" => escape_tex(text_content(children).trim()),
+        "mo" => operator(text_content(children).trim()),
+        "mtext" | "ms" => {
+            let text = text_content(children);
+            if text.trim().is_empty() {
+                " ".to_string()
+            } else {
+                format!("\\text{{{}}}", escape_tex(&text))
+            }
+        }
+        "mspace" => " ".to_string(),
+        "msup" => format!("{}^{}", group(&arg(0)), group(&arg(1))),
+        "msub" => format!("{}_{}", group(&arg(0)), group(&arg(1))),
+        "msubsup" => format!("{}_{}^{}", group(&arg(0)), group(&arg(1)), group(&arg(2))),
+        "mfrac" => format!("\\frac{{{}}}{{{}}}", arg(0), arg(1)),
+        "msqrt" => format!("\\sqrt{{{}}}", mathml_to_tex(children)),
+        "mroot" => format!("\\sqrt[{}]{{{}}}", arg(1), arg(0)),
+        "mover" => over_tex(&arg(0), &arg(1)),
+        "munder" => {
+            if is_large_operator(&arg(0)) {
+                format!("{}_{}", arg(0), group(&arg(1)))
+            } else {
+                format!("\\underset{{{}}}{{{}}}", arg(1), arg(0))
+            }
+        }
+        "munderover" => {
+            if is_large_operator(&arg(0)) {
+                format!("{}_{}^{}", arg(0), group(&arg(1)), group(&arg(2)))
+            } else {
+                format!(
+                    "\\underset{{{}}}{{\\overset{{{}}}{{{}}}}}",
+                    arg(1),
+                    arg(2),
+                    arg(0)
+                )
+            }
+        }
+        "mtable" => format!("\\begin{{matrix}} {} \\end{{matrix}}", args.join(" \\\\ ")),
+        "mtr" | "mlabeledtr" => args.join(" & "),
+        "mfenced" => {
+            let open = attr(attrs, "open").unwrap_or("(");
+            let close = attr(attrs, "close").unwrap_or(")");
+            let separator = attr(attrs, "separators")
+                .and_then(|value| value.trim().chars().next())
+                .unwrap_or(',');
+            format!(
+                "{}{}{}",
+                operator(open),
+                args.join(&separator.to_string()),
+                operator(close)
+            )
+        }
+        _ => mathml_to_tex(children),
+    }
+}
+
+// 追加 TeX 片段；命令名后紧跟字母时补空格，避免 \alpha x 变成 \alphax
+fn push_tex(output: &mut String, piece: &str) {
+    let ends_with_command = output.rsplit_once('\\').is_some_and(|(_, tail)| {
+        !tail.is_empty() && tail.chars().all(|ch| ch.is_ascii_alphabetic())
+    });
+    if ends_with_command && piece.starts_with(|ch: char| ch.is_ascii_alphabetic()) {
+        output.push(' ');
+    }
+    output.push_str(piece);
+}
+
+// 多于一个记号的参数加花括号
+fn group(tex: &str) -> String {
+    let is_command = tex.starts_with('\\') && tex[1..].chars().all(|ch| ch.is_ascii_alphabetic());
+    if tex.chars().count() == 1 || is_command {
+        tex.to_string()
+    } else {
+        format!("{{{tex}}}")
+    }
+}
+
+fn identifier(text: &str) -> String {
+    if FUNCTIONS.contains(&text) {
+        format!("\\{text}")
+    } else if text.chars().count() > 1 {
+        format!("\\mathrm{{{}}}", escape_tex(text))
+    } else {
+        escape_tex(text)
+    }
+}
+
+fn operator(text: &str) -> String {
+    match text {
+        // 函数应用、不可见乘号等不可见运算符
+        "\u{2061}" | "\u{2062}" | "\u{2063}" | "\u{2064}" => String::new(),
+        "{" => "\\{".to_string(),
+        "}" => "\\}".to_string(),
+        "−" => "-".to_string(),
+        _ => escape_tex(text),
+    }
+}
+
+fn over_tex(base: &str, over: &str) -> String {
+    let accent = match over {
+        "^" | "\\hat" | "ˆ" => Some("hat"),
+        "¯" | "‾" | "_" => Some("bar"),
+        "~" | "˜" | "\\sim" => Some("tilde"),
+        "\\to" => Some("vec"),
+        "˙" => Some("dot"),
+        "¨" => Some("ddot"),
+        _ => None,
+    };
+    match accent {
+        Some(accent) => format!("\\{accent}{{{base}}}"),
+        None if is_large_operator(base) => format!("{base}^{}", group(over)),
+        None => format!("\\overset{{{over}}}{{{base}}}"),
+    }
+}
+
+fn is_large_operator(tex: &str) -> bool {
+    matches!(tex, "\\sum" | "\\prod" | "\\int" | "\\oint" | "\\lim")
+}
+
+// 转义 TeX 特殊字符，并把常见 Unicode 符号替换为命令
+fn escape_tex(text: &str) -> String {
+    let mut output = String::new();
+    for ch in text.chars() {
+        let mut buf = [0u8; 4];
+        let symbol = ch.encode_utf8(&mut buf);
+        if let Some((_, command)) = SYMBOLS.iter().find(|(unicode, _)| *unicode == symbol) {
+            push_tex(&mut output, command);
+        } else if let Some((_, letter)) = BLACKBOARD.iter().find(|(unicode, _)| *unicode == ch) {
+            push_tex(&mut output, &format!("\\mathbb{{{letter}}}"));
+        } else {
+            match ch {
+                '{' | '}' | '%' | '#' | '&' | '$' | '_' => {
+                    output.push('\\');
+                    output.push(ch);
+                }
+                '\\' => output.push_str("\\backslash "),
+                _ => push_tex(&mut output, symbol),
+            }
+        }
+    }
+    output
+}
+
+// 纯文本输出中的线性化公式，例如 \frac{a+b}{2} → (a+b)/2，x^{2} → x^2
+pub fn linearize(tex: &str) -> String {
+    let chars: Vec<char> = tex.chars().collect();
+    let mut pos = 0;
+    let output = linearize_until(&chars, &mut pos, None);
+    collapse_whitespace(&output)
+}
+
+fn linearize_until(chars: &[char], pos: &mut usize, end: Option<char>) -> String {
+    let mut output = String::new();
+    while *pos < chars.len() {
+        let ch = chars[*pos];
+        if Some(ch) == end {
+            *pos += 1;
+            break;
+        }
+        *pos += 1;
+        match ch {
+            '{' => output.push_str(&linearize_until(chars, pos, Some('}'))),
+            '^' | '_' => {
+                let arg = linearize_arg(chars, pos);
+                output.push(ch);
+                output.push_str(&parenthesize(&arg));
+            }
+            '&' | '~' => output.push(' '),
+            '\\' => output.push_str(&linearize_command(chars, pos)),
+            _ => output.push(ch),
+        }
+    }
+    output
+}
+
+// 读取一个参数：花括号分组、命令或单个字符
+fn linearize_arg(chars: &[char], pos: &mut usize) -> String {
+    while *pos < chars.len() && chars[*pos].is_whitespace() {
+        *pos += 1;
+    }
+    match chars.get(*pos) {
+        Some('{') => {
+            *pos += 1;
+            linearize_until(chars, pos, Some('}'))
+        }
+        Some('\\') => {
+            *pos += 1;
+            linearize_command(chars, pos)
+        }
+        Some(ch) => {
+            *pos += 1;
+            ch.to_string()
+        }
+        None => String::new(),
+    }
+}
+
+fn linearize_command(chars: &[char], pos: &mut usize) -> String {
+    let start = *pos;
+    while *pos < chars.len() && chars[*pos].is_ascii_alphabetic() {
+        *pos += 1;
+    }
+    if *pos == start {
+        // 单字符命令：\\ 换行、\, 等间距、\{ 等转义
+        let Some(&ch) = chars.get(*pos) else {
+            return String::new();
+        };
+        *pos += 1;
+        return match ch {
+            '\\' => "; ".to_string(),
+            ',' | ';' | ':' | ' ' => " ".to_string(),
+            '!' => String::new(),
+            _ => ch.to_string(),
+        };
+    }
+
+    let name: String = chars[start..*pos].iter().collect();
+    let command = format!("\\{name}");
+    match name.as_str() {
+        "frac" | "dfrac" | "tfrac" => {
+            let numerator = linearize_arg(chars, pos);
+            let denominator = linearize_arg(chars, pos);
+            format!(
+                "{}/{}",
+                parenthesize(&numerator),
+                parenthesize(&denominator)
+            )
+        }
+        "sqrt" => {
+            let mut index = String::new();
+            if chars.get(*pos) == Some(&'[') {
+                *pos += 1;
+                index = linearize_until(chars, pos, Some(']'));
+            }
+            format!("{index}√{}", parenthesize(&linearize_arg(chars, pos)))
+        }
+        "mathbb" => {
+            let arg = linearize_arg(chars, pos);
+            BLACKBOARD
+                .iter()
+                .find(|(_, letter)| *letter == arg)
+                .map_or(arg, |(unicode, _)| unicode.to_string())
+        }
+        "begin" | "end" => {
+            linearize_arg(chars, pos);
+            String::new()
+        }
+        "quad" | "qquad" => " ".to_string(),
+        "cdot" => "·".to_string(),
+        _ if STYLE_COMMANDS.contains(&name.as_str()) => linearize_arg(chars, pos),
+        _ if LAYOUT_COMMANDS.contains(&name.as_str()) => {
+            // \left. / \right. 表示不显示的定界符
+            if matches!(name.as_str(), "left" | "right") && chars.get(*pos) == Some(&'.') {
+                *pos += 1;
+            }
+            String::new()
+        }
+        _ if FUNCTIONS.contains(&name.as_str()) => format!("{name} "),
+        _ => {
+            if let Some((_, mark)) = ACCENTS.iter().find(|(accent, _)| *accent == name) {
+                let mut arg = linearize_arg(chars, pos);
+                arg.push(*mark);
+                return arg;
+            }
+            SYMBOLS
+                .iter()
+                .find(|(_, tex)| *tex == command)
+                .map(|(unicode, _)| unicode.to_string())
+                .or_else(|| {
+                    SYMBOL_ALIASES
+                        .iter()
+                        .find(|(tex, _)| *tex == command)
+                        .map(|(_, unicode)| unicode.to_string())
+                })
+                .unwrap_or(name)
+        }
+    }
+}
+
+// 单个字符或数字以外的参数加括号，保证线性化后结合关系不变，例如 √(ab)、e^(iπ)
+fn parenthesize(text: &str) -> String {
+    let text = text.trim();
+    if text.chars().count() <= 1 || text.chars().all(|ch| ch.is_ascii_digit() || ch == '.') {
+        text.to_string()
+    } else {
+        format!("({text})")
+    }
+}
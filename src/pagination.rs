@@ -0,0 +1,256 @@
+use url::Url;
+
+use crate::{
+    parser::{Attr, HtmlNode, attr},
+    urls::resolve_href,
+};
+
+pub const MAX_PAGES: usize = 20;
+
+// 常见的“下一页”链接文字（小写，已去掉箭头符号）
+const NEXT_WORDS: [&str; 15] = [
+    "next",
+    "next page",
+    "older",
+    "older posts",
+    "older entries",
+    "下一页",
+    "下页",
+    "后一页",
+    "下一頁",
+    "次へ",
+    "次のページ",
+    "다음",
+    "suivant",
+    "weiter",
+    "siguiente",
+];
+const ARROWS: [char; 6] = ['»', '›', '→', '>', '⟩', '▶'];
+const PAGE_PARAMS: [&str; 4] = ["page", "p", "pg", "paged"];
+// class / id 中出现这些片段的元素视为分页器容器
+const PAGINATOR_HINTS: [&str; 4] = ["pagination", "pager", "paging", "page-numbers"];
+
+struct Anchor {
+    href: String,
+    text: String,
+    rel: String,
+    hints: String,
+    // 位于 <nav>、role=navigation 或分页器类名的容器中
+    in_paginator: bool,
+}
+
+#[derive(Default)]
+struct PageLinks {
+    rel_next: Option<String>,
+    anchors: Vec<Anchor>,
+    // 分页器中标记为当前页的数字（aria-current / current / active）
+    current: Option<u32>,
+}
+
+// 依次尝试 rel=next、“下一页”文字/属性、分页器容器中的数字链接，返回下一页的绝对地址
+pub fn find_next_page(nodes: &[HtmlNode], page_url: &str) -> Option<String> {
+    let mut links = PageLinks::default();
+    for node in nodes {
+        collect_links(node, &mut links, false);
+    }
+
+    let resolve = |href: &str| resolve_href(href, page_url).filter(|url| !same_page(url, page_url));
+
+    if let Some(url) = links.rel_next.as_deref().and_then(resolve) {
+        return Some(url);
+    }
+
+    if let Some(url) = links
+        .anchors
+        .iter()
+        .filter(|anchor| anchor.rel.split_whitespace().any(|token| token == "next"))
+        .find_map(|anchor| resolve(&anchor.href))
+    {
+        return Some(url);
+    }
+
+    if let Some(url) = links
+        .anchors
+        .iter()
+        .filter(|anchor| is_next_text(&anchor.text) || is_next_hint(&anchor.hints))
+        .find_map(|anchor| resolve(&anchor.href))
+    {
+        return Some(url);
+    }
+
+    numbered_next(&links, page_url).and_then(|href| resolve(&href))
+}
+
+fn collect_links(node: &HtmlNode, links: &mut PageLinks, in_paginator: bool) {
+    let HtmlNode::Element {
+        tag,
+        attrs,
+        children,
+    } = node
+    else {
+        return;
+    };
+    let in_paginator = in_paginator || is_paginator(tag, attrs);
+
+    match tag.as_str() {
+        "link" => {
+            if links.rel_next.is_none()
+                && attr(attrs, "rel").is_some_and(|rel| {
+                    rel.to_ascii_lowercase()
+                        .split_whitespace()
+                        .any(|t| t == "next")
+                })
+            {
+                links.rel_next = attr(attrs, "href").map(|href| href.to_string());
+            }
+            return;
+        }
+        "a" => {
+            let text = node_text(children);
+            if let Some(href) = attr(attrs, "href") {
+                links.anchors.push(Anchor {
+                    href: href.to_string(),
+                    text: text.clone(),
+                    rel: attr(attrs, "rel").unwrap_or_default().to_ascii_lowercase(),
+                    hints: ["aria-label", "title", "class", "id"]
+                        .iter()
+                        .filter_map(|name| attr(attrs, name))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                        .to_lowercase(),
+                    in_paginator,
+                });
+            }
+            if in_paginator
+                && is_current_marker(attrs)
+                && let Ok(number) = text.parse::<u32>()
+            {
+                links.current.get_or_insert(number);
+            }
+            return;
+        }
+        _ => {}
+    }
+
+    if in_paginator
+        && is_current_marker(attrs)
+        && let Ok(number) = node_text(children).parse::<u32>()
+    {
+        links.current.get_or_insert(number);
+    }
+
+    for child in children {
+        collect_links(child, links, in_paginator);
+    }
+}
+
+fn is_paginator(tag: &str, attrs: &[Attr]) -> bool {
+    if tag == "nav" || attr(attrs, "role").is_some_and(|role| role == "navigation") {
+        return true;
+    }
+    ["class", "id"]
+        .iter()
+        .filter_map(|name| attr(attrs, name))
+        .any(|value| {
+            let value = value.to_ascii_lowercase();
+            PAGINATOR_HINTS.iter().any(|hint| value.contains(hint))
+        })
+}
+
+fn node_text(children: &[HtmlNode]) -> String {
+    fn walk(node: &HtmlNode, out: &mut String) {
+        match node {
+            HtmlNode::Text(text) => {
+                out.push_str(text);
+                out.push(' ');
+            }
+            HtmlNode::Element { children, .. } => {
+                for child in children {
+                    walk(child, out);
+                }
+            }
+        }
+    }
+
+    let mut out = String::new();
+    for child in children {
+        walk(child, &mut out);
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn is_current_marker(attrs: &[Attr]) -> bool {
+    attr(attrs, "aria-current").is_some_and(|value| value == "page")
+        || attr(attrs, "class").is_some_and(|class| {
+            class
+                .split_whitespace()
+                .any(|token| matches!(token, "current" | "active" | "selected" | "is-current"))
+        })
+}
+
+fn is_next_text(text: &str) -> bool {
+    let lower = text.to_lowercase();
+    let word = lower.trim_matches(|ch: char| ch.is_whitespace() || ARROWS.contains(&ch));
+    if word.is_empty() {
+        // 只有箭头的链接：单个 » 或 › 通常是下一页，»» 之类多为末页
+        return matches!(lower.trim(), "»" | "›" | "→" | ">");
+    }
+    NEXT_WORDS.contains(&word)
+}
+
+fn is_next_hint(hints: &str) -> bool {
+    hints
+        .split(|ch: char| ch.is_whitespace() || ch == '-' || ch == '_')
+        .any(|token| token == "next")
+}
+
+fn numbered_next(links: &PageLinks, page_url: &str) -> Option<String> {
+    let numbered: Vec<(u32, &Anchor)> = links
+        .anchors
+        .iter()
+        .filter(|anchor| anchor.in_paginator)
+        .filter_map(|anchor| anchor.text.parse::<u32>().ok().map(|n| (n, anchor)))
+        .collect();
+    // 只看分页器容器中的数字链接，且至少两个，避免误把正文或页脚中的编号当作页码
+    if numbered.len() < 2 {
+        return None;
+    }
+
+    let current = links
+        .current
+        .or_else(|| page_number_from_url(page_url))
+        .unwrap_or(1);
+    numbered
+        .iter()
+        .find(|(n, _)| *n == current + 1)
+        .map(|(_, anchor)| anchor.href.clone())
+}
+
+fn page_number_from_url(page_url: &str) -> Option<u32> {
+    let url = Url::parse(page_url).ok()?;
+    if let Some(number) = url
+        .query_pairs()
+        .find(|(key, _)| PAGE_PARAMS.contains(&key.as_ref()))
+        .and_then(|(_, value)| value.parse::<u32>().ok())
+    {
+        return Some(number);
+    }
+
+    let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+    segments
+        .windows(2)
+        .rev()
+        .find(|pair| pair[0] == "page")
+        .and_then(|pair| pair[1].parse::<u32>().ok())
+}
+
+fn same_page(a: &str, b: &str) -> bool {
+    let strip = |url: &str| {
+        url.split('#')
+            .next()
+            .unwrap_or(url)
+            .trim_end_matches('/')
+            .to_string()
+    };
+    strip(a) == strip(b)
+}
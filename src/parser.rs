@@ -16,6 +16,22 @@ pub struct Attr {
     pub value: String,
 }
 
+// 按名称取属性值，重复的属性以第一个为准
+pub fn attr<'a>(attrs: &'a [Attr], name: &str) -> Option<&'a str> {
+    attrs
+        .iter()
+        .find(|attr| attr.name == name)
+        .map(|attr| attr.value.as_str())
+}
+
+// class 属性中按空白拆分的各个类名
+pub fn classes(attrs: &[Attr]) -> impl Iterator<Item = &str> {
+    attrs
+        .iter()
+        .filter(|attr| attr.name == "class")
+        .flat_map(|attr| attr.value.split_whitespace())
+}
+
 #[derive(Debug, PartialEq)]
 enum ParserState {
     Text,
@@ -93,7 +109,8 @@ impl HtmlParser {
         if start < self.pos {
             let text: String = self.input[start..self.pos].iter().collect();
             let decoded = decode_entities(&text);
-            if !decoded.trim().is_empty() {
+            // <pre> 中高亮标签之间的空白属于代码本身，需要保留
+            if !decoded.trim().is_empty() || self.stack.iter().any(|elem| elem.tag == "pre") {
                 self.add_node(HtmlNode::Text(decoded));
             }
         }
@@ -320,6 +337,27 @@ impl HtmlParser {
                 self.add_node(self.element_to_node(elem));
             }
             self.state = ParserState::Text;
+        } else if tag == "script" && self.stack.last().is_some_and(is_math_script) {
+            // MathJax v2 的公式源码保存在 <script type="math/tex"> 中，保留原文
+            let start = self.pos;
+            self.skip_until_close_tag(tag);
+            let close_len = "</script>".len();
+            let closed = self.pos >= start + close_len
+                && self.input[self.pos - close_len..self.pos]
+                    .iter()
+                    .collect::<String>()
+                    .eq_ignore_ascii_case("</script>");
+            let end = if closed {
+                self.pos - close_len
+            } else {
+                self.pos
+            };
+            let source: String = self.input[start..end].iter().collect();
+            if let Some(mut elem) = self.stack.pop() {
+                elem.children.push(HtmlNode::Text(source));
+                self.add_node(self.element_to_node(elem));
+            }
+            self.state = ParserState::Text;
         } else if should_skip_content(tag) {
             self.skip_until_close_tag(tag);
             let _ = self.stack.pop();
@@ -416,6 +454,12 @@ fn is_void_element(tag: &str) -> bool {
     )
 }
 
+fn is_math_script(elem: &Element) -> bool {
+    elem.attrs.iter().any(|attr| {
+        attr.name == "type" && attr.value.trim().to_ascii_lowercase().starts_with("math/")
+    })
+}
+
 fn should_skip_content(tag: &str) -> bool {
     matches!(tag, "script" | "style" | "noscript")
 }
@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+
+use pdf_extract::{Document, MediaBox, OutputDev, OutputError, Transform};
+
+use crate::word_count::is_cjk_like;
+
+const HEADING_SIZE_RATIO: f64 = 1.15;
+const MAX_HEADING_LEVELS: usize = 3;
+const MAX_HEADING_CHARS: usize = 200;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PdfBlock {
+    Heading { level: u8, text: String },
+    Paragraph(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct PdfPage {
+    pub number: u32,
+    pub blocks: Vec<PdfBlock>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PdfDocument {
+    pub pages: Vec<PdfPage>,
+}
+
+impl PdfDocument {
+    pub fn page_count(&self) -> u32 {
+        self.pages.len() as u32
+    }
+}
+
+pub fn extract_pdf(bytes: &[u8]) -> Result<PdfDocument, String> {
+    let mut doc = Document::load_mem(bytes).map_err(|e| format!("load pdf failed: {e}"))?;
+    if doc.is_encrypted() {
+        doc.decrypt("")
+            .map_err(|e| format!("decrypt pdf failed: {e}"))?;
+    }
+
+    let mut collector = LineCollector::default();
+    pdf_extract::output_doc(&doc, &mut collector)
+        .map_err(|e| format!("extract pdf text failed: {e}"))?;
+
+    Ok(build_document(collector.pages))
+}
+
+pub fn pdf_to_markdown(doc: &PdfDocument) -> String {
+    let mut output = String::new();
+    for page in &doc.pages {
+        output.push_str(&format!("<!-- page {} -->\n\n", page.number));
+        for block in &page.blocks {
+            match block {
+                PdfBlock::Heading { level, text } => {
+                    output.push_str(&format!("{} {}\n\n", "#".repeat(*level as usize), text));
+                }
+                PdfBlock::Paragraph(text) => {
+                    output.push_str(text);
+                    output.push_str("\n\n");
+                }
+            }
+        }
+    }
+    output.trim().to_string()
+}
+
+pub fn pdf_to_text(doc: &PdfDocument) -> String {
+    let mut output = String::new();
+    for page in &doc.pages {
+        output.push_str(&format!("--- page {} ---\n", page.number));
+        for block in &page.blocks {
+            match block {
+                PdfBlock::Heading { text, .. } | PdfBlock::Paragraph(text) => {
+                    output.push_str(text);
+                    output.push('\n');
+                }
+            }
+        }
+        output.push('\n');
+    }
+    output.trim().to_string()
+}
+
+#[derive(Debug, Clone)]
+struct Line {
+    text: String,
+    font_size: f64,
+    gap_before: bool,
+}
+
+#[derive(Debug, Default)]
+struct CollectedPage {
+    number: u32,
+    lines: Vec<Line>,
+}
+
+#[derive(Default)]
+struct LineCollector {
+    pages: Vec<CollectedPage>,
+    page_height: f64,
+    last_end: f64,
+    last_y: f64,
+    first_char: bool,
+    pending_gap: bool,
+}
+
+impl LineCollector {
+    fn current_line(&mut self) -> Option<&mut Line> {
+        self.pages.last_mut().and_then(|page| page.lines.last_mut())
+    }
+
+    fn start_line(&mut self, font_size: f64) {
+        let gap_before = self.pending_gap;
+        self.pending_gap = false;
+        if let Some(page) = self.pages.last_mut() {
+            page.lines.push(Line {
+                text: String::new(),
+                font_size,
+                gap_before,
+            });
+        }
+    }
+}
+
+impl OutputDev for LineCollector {
+    fn begin_page(
+        &mut self,
+        page_num: u32,
+        media_box: &MediaBox,
+        _art_box: Option<(f64, f64, f64, f64)>,
+    ) -> Result<(), OutputError> {
+        self.pages.push(CollectedPage {
+            number: page_num,
+            lines: Vec::new(),
+        });
+        self.page_height = media_box.ury - media_box.lly;
+        self.last_end = f64::MAX;
+        self.last_y = 0.0;
+        self.pending_gap = false;
+        Ok(())
+    }
+
+    fn end_page(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn output_character(
+        &mut self,
+        trm: &Transform,
+        width: f64,
+        _spacing: f64,
+        font_size: f64,
+        ch: &str,
+    ) -> Result<(), OutputError> {
+        // 翻转 y 轴，使页面顶部为 0
+        let x = trm.m31;
+        let y = self.page_height - trm.m32;
+        let size_x = font_size * trm.m11 + font_size * trm.m21;
+        let size_y = font_size * trm.m12 + font_size * trm.m22;
+        let size = (size_x * size_y).abs().sqrt().max(1.0);
+
+        let dy = (y - self.last_y).abs();
+        let has_line = self.current_line().is_some();
+        let needs_new_line = !has_line
+            || (self.first_char && (dy > size * 1.5 || (x < self.last_end && dy > size * 0.5)));
+
+        if needs_new_line {
+            if has_line && dy > size * 1.8 {
+                self.pending_gap = true;
+            }
+            self.start_line(size);
+        } else if self.first_char
+            && x > self.last_end + size * 0.1
+            && let Some(line) = self.current_line()
+        {
+            line.text.push(' ');
+        }
+
+        if let Some(line) = self.current_line() {
+            line.text.push_str(ch);
+            if size > line.font_size {
+                line.font_size = size;
+            }
+        }
+
+        self.first_char = false;
+        self.last_y = y;
+        self.last_end = x + width * size;
+        Ok(())
+    }
+
+    fn begin_word(&mut self) -> Result<(), OutputError> {
+        self.first_char = true;
+        Ok(())
+    }
+
+    fn end_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_line(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+}
+
+fn size_key(size: f64) -> i64 {
+    (size * 2.0).round() as i64
+}
+
+// 按字符数加权取出现最多的字号作为正文字号
+fn body_font_size(pages: &[CollectedPage]) -> f64 {
+    let mut weights: HashMap<i64, usize> = HashMap::new();
+    for line in pages.iter().flat_map(|page| page.lines.iter()) {
+        *weights.entry(size_key(line.font_size)).or_default() += line.text.chars().count();
+    }
+    weights
+        .into_iter()
+        .max_by_key(|(key, weight)| (*weight, -*key))
+        .map(|(key, _)| key as f64 / 2.0)
+        .unwrap_or(0.0)
+}
+
+fn heading_levels(pages: &[CollectedPage], body_size: f64) -> Vec<i64> {
+    let mut sizes: Vec<i64> = pages
+        .iter()
+        .flat_map(|page| page.lines.iter())
+        .filter(|line| is_heading_candidate(line, body_size))
+        .map(|line| size_key(line.font_size))
+        .collect();
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    sizes.dedup();
+    sizes
+}
+
+fn is_heading_candidate(line: &Line, body_size: f64) -> bool {
+    let text = line.text.trim();
+    body_size > 0.0
+        && line.font_size >= body_size * HEADING_SIZE_RATIO
+        && !text.is_empty()
+        && text.chars().count() <= MAX_HEADING_CHARS
+}
+
+fn build_document(pages: Vec<CollectedPage>) -> PdfDocument {
+    let body_size = body_font_size(&pages);
+    let levels = heading_levels(&pages, body_size);
+
+    let pages = pages
+        .iter()
+        .map(|page| {
+            let mut blocks: Vec<PdfBlock> = Vec::new();
+            let mut paragraph = String::new();
+            let mut last_heading_size: Option<i64> = None;
+
+            for line in &page.lines {
+                let text = line.text.trim();
+                if text.is_empty() {
+                    continue;
+                }
+
+                if is_heading_candidate(line, body_size) {
+                    flush_paragraph(&mut paragraph, &mut blocks);
+                    let key = size_key(line.font_size);
+                    let level = levels
+                        .iter()
+                        .position(|size| *size == key)
+                        .unwrap_or(0)
+                        .min(MAX_HEADING_LEVELS - 1)
+                        + 1;
+
+                    // 同字号的连续标题行合并为一个标题
+                    if last_heading_size == Some(key)
+                        && !line.gap_before
+                        && let Some(PdfBlock::Heading { text: prev, .. }) = blocks.last_mut()
+                    {
+                        prev.push(' ');
+                        prev.push_str(text);
+                    } else {
+                        blocks.push(PdfBlock::Heading {
+                            level: level as u8,
+                            text: text.to_string(),
+                        });
+                    }
+                    last_heading_size = Some(key);
+                    continue;
+                }
+
+                last_heading_size = None;
+                if line.gap_before {
+                    flush_paragraph(&mut paragraph, &mut blocks);
+                }
+                append_line(&mut paragraph, text);
+            }
+            flush_paragraph(&mut paragraph, &mut blocks);
+
+            PdfPage {
+                number: page.number,
+                blocks,
+            }
+        })
+        .collect();
+
+    PdfDocument { pages }
+}
+
+// 行尾连字符且下一行以小写字母开头时视为断词，直接拼接
+fn append_line(paragraph: &mut String, text: &str) {
+    if paragraph.is_empty() {
+        paragraph.push_str(text);
+        return;
+    }
+    let continues_word = paragraph.ends_with('-')
+        && text
+            .chars()
+            .next()
+            .map(|ch| ch.is_lowercase())
+            .unwrap_or(false);
+    let joins_cjk = paragraph.chars().last().is_some_and(is_cjk_like)
+        && text.chars().next().is_some_and(is_cjk_like);
+    if continues_word {
+        paragraph.pop();
+    } else if !joins_cjk {
+        paragraph.push(' ');
+    }
+    paragraph.push_str(text);
+}
+
+fn flush_paragraph(paragraph: &mut String, blocks: &mut Vec<PdfBlock>) {
+    let text = paragraph.trim();
+    if !text.is_empty() {
+        blocks.push(PdfBlock::Paragraph(text.to_string()));
+    }
+    paragraph.clear();
+}
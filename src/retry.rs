@@ -0,0 +1,101 @@
+use std::{env, time::Duration};
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+
+use crate::fetch_error::FetchError;
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+const DEFAULT_BASE_DELAY_MS: u64 = 500;
+const DEFAULT_MAX_DELAY_MS: u64 = 10_000;
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            base_delay: Duration::from_millis(DEFAULT_BASE_DELAY_MS),
+            max_delay: Duration::from_millis(DEFAULT_MAX_DELAY_MS),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            max_attempts: env_parse("RETRY_MAX_ATTEMPTS")
+                .map(|v: u32| v.max(1))
+                .unwrap_or(default.max_attempts),
+            base_delay: env_parse("RETRY_BASE_DELAY_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(default.base_delay),
+            max_delay: env_parse("RETRY_MAX_DELAY_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(default.max_delay),
+        }
+    }
+
+    // attempt 从 1 开始，表示刚刚失败的是第几次尝试
+    pub fn delay_for(&self, attempt: u32, error: &FetchError) -> Duration {
+        if let Some(retry_after) = error.retry_after() {
+            return retry_after.min(self.max_delay);
+        }
+
+        let exp = attempt.saturating_sub(1).min(16);
+        let backoff = self
+            .base_delay
+            .saturating_mul(1u32 << exp)
+            .min(self.max_delay);
+
+        // 等比抖动：在 [backoff/2, backoff] 之间随机，避免批量请求同时重试
+        let half = backoff / 2;
+        let jitter_ms = rand::random_range(0..=half.as_millis() as u64);
+        half + Duration::from_millis(jitter_ms)
+    }
+
+    pub async fn run<T, F, Fut>(&self, url: &str, mut op: F) -> Result<T, FetchError>
+    where
+        F: FnMut(bool) -> Fut,
+        Fut: Future<Output = Result<T, FetchError>>,
+    {
+        let mut attempt = 1;
+        loop {
+            let last_attempt = attempt >= self.max_attempts;
+            match op(last_attempt).await {
+                Ok(value) => return Ok(value),
+                Err(err) if !last_attempt && err.is_retryable() => {
+                    let delay = self.delay_for(attempt, &err);
+                    tracing::debug!(
+                        "fetch {url} attempt {attempt} failed ({:?}): {err}; retrying in {delay:?}",
+                        err.kind()
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+pub fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(std::time::SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+fn env_parse<T: std::str::FromStr>(key: &str) -> Option<T> {
+    env::var(key).ok().and_then(|v| v.trim().parse::<T>().ok())
+}
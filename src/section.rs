@@ -0,0 +1,279 @@
+use crate::{
+    parser::HtmlNode,
+    text,
+    toc::{self, AnchorSet},
+};
+
+// 章节容器：匹配的标题位于其中时，离开该元素即结束章节，避免带上页脚等后续内容
+const SECTIONING_TAGS: [&str; 2] = ["section", "article"];
+
+// 未找到章节时错误信息中列出的标题数量上限
+const MAX_LISTED_HEADINGS: usize = 20;
+
+struct HeadingInfo {
+    level: u8,
+    text: String,
+    anchor: String,
+}
+
+// 只保留从匹配标题开始、到下一个同级或更高级标题之前的内容，祖先元素保留为外层结构。
+// query 依次按锚点、标题文字（忽略大小写与多余空白）、由 query 生成的锚点、标题文字包含 query 匹配
+pub fn extract_section(mut nodes: Vec<HtmlNode>, query: &str) -> Result<Vec<HtmlNode>, String> {
+    toc::assign_anchors(&mut nodes);
+    let mut headings = Vec::new();
+    collect_headings(&nodes, &mut headings);
+
+    let Some(target) = find_heading(&headings, query) else {
+        return Err(not_found_message(&headings, query));
+    };
+    let level = headings[target].level;
+
+    let mut cursor = Cursor {
+        target,
+        level,
+        seen: 0,
+        state: State::Before,
+        depth: 0,
+        boundary: None,
+    };
+    Ok(cursor.slice(&nodes))
+}
+
+// Markdown 文档按 ATX 标题（# 到 ######）截取章节，匹配规则与 HTML 相同；围栏代码块内的 # 行不视为标题
+pub fn extract_markdown_section(markdown: &str, query: &str) -> Result<String, String> {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let mut anchors = AnchorSet::default();
+    let mut headings = Vec::new();
+    let mut starts = Vec::new();
+    let mut fence: Option<(char, usize)> = None;
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        let marker = trimmed.chars().next().filter(|ch| matches!(ch, '`' | '~'));
+        if let Some(ch) = marker {
+            let run = trimmed.chars().take_while(|c| *c == ch).count();
+            match fence {
+                None if run >= 3 => fence = Some((ch, run)),
+                Some((open, len))
+                    if open == ch && run >= len && trimmed[run..].trim().is_empty() =>
+                {
+                    fence = None
+                }
+                _ => {}
+            }
+            continue;
+        }
+        if fence.is_some() {
+            continue;
+        }
+        if let Some((level, text)) = atx_heading(line) {
+            let anchor = anchors.assign(&[], &[], &text);
+            headings.push(HeadingInfo {
+                level,
+                text,
+                anchor,
+            });
+            starts.push(index);
+        }
+    }
+
+    let Some(target) = find_heading(&headings, query) else {
+        return Err(not_found_message(&headings, query));
+    };
+    let level = headings[target].level;
+    let end = headings
+        .iter()
+        .zip(&starts)
+        .skip(target + 1)
+        .find(|(heading, _)| heading.level <= level)
+        .map(|(_, start)| *start)
+        .unwrap_or(lines.len());
+    Ok(lines[starts[target]..end].join("\n").trim_end().to_string())
+}
+
+// 最多 3 个空格缩进，1–6 个 # 后跟空白或行尾；去掉结尾的闭合 #
+fn atx_heading(line: &str) -> Option<(u8, String)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+    let rest = &line[indent..];
+    let level = rest.chars().take_while(|ch| *ch == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &rest[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    let mut text = rest.trim();
+    let closing = text.trim_end_matches('#');
+    if closing.is_empty() || closing.ends_with([' ', '\t']) {
+        text = closing.trim_end();
+    }
+    (!text.is_empty()).then(|| (level as u8, text.to_string()))
+}
+
+fn find_heading(headings: &[HeadingInfo], query: &str) -> Option<usize> {
+    let query = query.trim().trim_start_matches('#');
+    if query.is_empty() {
+        return None;
+    }
+    let normalized = normalize(query);
+    let slug = toc::slugify(query);
+
+    let position = |matches: &dyn Fn(&HeadingInfo) -> bool| headings.iter().position(matches);
+    position(&|heading| heading.anchor == query)
+        .or_else(|| position(&|heading| normalize(&heading.text) == normalized))
+        .or_else(|| position(&|heading| heading.anchor.eq_ignore_ascii_case(&slug)))
+        .or_else(|| position(&|heading| normalize(&heading.text).contains(&normalized)))
+}
+
+fn not_found_message(headings: &[HeadingInfo], query: &str) -> String {
+    if headings.is_empty() {
+        return format!("{query} (page has no headings)");
+    }
+    let mut listed: Vec<String> = headings
+        .iter()
+        .take(MAX_LISTED_HEADINGS)
+        .map(|heading| format!("{} (#{})", heading.text, heading.anchor))
+        .collect();
+    if headings.len() > MAX_LISTED_HEADINGS {
+        listed.push(format!("... {} more", headings.len() - MAX_LISTED_HEADINGS));
+    }
+    format!("{query} (available headings: {})", listed.join(", "))
+}
+
+// 锚点取自 toc::assign_anchors 的结果，截取后的节点仍带着它，转换时输出相同的锚点
+fn collect_headings(nodes: &[HtmlNode], headings: &mut Vec<HeadingInfo>) {
+    for node in nodes {
+        let HtmlNode::Element {
+            tag,
+            attrs,
+            children,
+        } = node
+        else {
+            continue;
+        };
+        match heading_level(tag) {
+            Some(level) => {
+                let text = text::inline_text(children);
+                if !text.is_empty()
+                    && let Some(anchor) = toc::assigned_anchor(attrs)
+                {
+                    let anchor = anchor.to_string();
+                    headings.push(HeadingInfo {
+                        level,
+                        text,
+                        anchor,
+                    });
+                }
+            }
+            None => collect_headings(children, headings),
+        }
+    }
+}
+
+fn heading_level(tag: &str) -> Option<u8> {
+    match tag {
+        "h1" => Some(1),
+        "h2" => Some(2),
+        "h3" => Some(3),
+        "h4" => Some(4),
+        "h5" => Some(5),
+        "h6" => Some(6),
+        _ => None,
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[derive(PartialEq)]
+enum State {
+    Before,
+    Inside,
+    After,
+}
+
+struct Cursor {
+    // 目标标题在非空标题中的序号及其级别
+    target: usize,
+    level: u8,
+    seen: usize,
+    state: State,
+    // 当前所在章节容器的层数，以及匹配的标题所在容器的层数
+    depth: usize,
+    boundary: Option<usize>,
+}
+
+impl Cursor {
+    fn slice(&mut self, nodes: &[HtmlNode]) -> Vec<HtmlNode> {
+        let mut kept = Vec::new();
+        for node in nodes {
+            if self.state == State::After {
+                break;
+            }
+            match node {
+                HtmlNode::Text(_) => {
+                    if self.state == State::Inside {
+                        kept.push(node.clone());
+                    }
+                }
+                HtmlNode::Element {
+                    tag,
+                    attrs,
+                    children,
+                } => {
+                    if let Some(level) = heading_level(tag)
+                        && !text::inline_text(children).is_empty()
+                    {
+                        let index = self.seen;
+                        self.seen += 1;
+                        if index == self.target {
+                            self.state = State::Inside;
+                            self.boundary = (self.depth > 0).then_some(self.depth);
+                        } else if self.state == State::Inside && level <= self.level {
+                            self.state = State::After;
+                            break;
+                        }
+                        if self.state == State::Inside {
+                            kept.push(node.clone());
+                        }
+                        continue;
+                    }
+
+                    // 进入时已在章节内的元素即使没有子节点（如 <img>、<hr>）也保留
+                    let was_inside = self.state == State::Inside;
+                    let sectioning = SECTIONING_TAGS.contains(&tag.as_str());
+                    if sectioning {
+                        self.depth += 1;
+                    }
+                    let children = self.slice(children);
+                    if sectioning {
+                        // 容器中只有标题本身时（仅用于包裹标题），章节继续向后延伸
+                        if self.state == State::Inside && self.boundary == Some(self.depth) {
+                            if children.len() > 1 {
+                                self.state = State::After;
+                            } else {
+                                self.boundary = None;
+                            }
+                        }
+                        self.depth -= 1;
+                    }
+                    if was_inside || !children.is_empty() {
+                        kept.push(HtmlNode::Element {
+                            tag: tag.clone(),
+                            attrs: attrs.clone(),
+                            children,
+                        });
+                    }
+                }
+            }
+        }
+        kept
+    }
+}
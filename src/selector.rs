@@ -0,0 +1,575 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::parser::{self, Attr, HtmlNode};
+
+// 支持的 CSS 选择器子集：类型、通配符、#id、.class、属性（= ~= |= ^= $= *=）、
+// 后代与子代组合符、:nth-child / :first-child / :last-child，以及逗号分隔的选择器列表
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    alternatives: Vec<Complex>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Complex {
+    // compounds[0] 为最左侧；combinators[i] 连接 compounds[i] 与 compounds[i + 1]
+    compounds: Vec<Compound>,
+    combinators: Vec<Combinator>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Compound {
+    tag: Option<String>,
+    conditions: Vec<Condition>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Id(String),
+    Class(String),
+    Attr {
+        name: String,
+        op: Option<(AttrOp, String)>,
+    },
+    NthChild {
+        a: i64,
+        b: i64,
+    },
+    LastChild,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AttrOp {
+    Equals,
+    Includes,
+    DashMatch,
+    Prefix,
+    Suffix,
+    Substring,
+}
+
+// 匹配时使用的祖先链元素：节点及其在同级元素中的位置（从 1 开始）
+#[derive(Clone, Copy)]
+struct Ctx<'a> {
+    node: &'a HtmlNode,
+    index: usize,
+    sibling_count: usize,
+}
+
+impl Selector {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut alternatives = Vec::new();
+        for part in split_top_level(input) {
+            let part = part.trim();
+            if part.is_empty() {
+                return Err(format!("empty selector in list: {input}"));
+            }
+            alternatives.push(parse_complex(part)?);
+        }
+        if alternatives.is_empty() {
+            return Err("empty selector".to_string());
+        }
+        Ok(Self { alternatives })
+    }
+
+    // 按文档顺序返回所有匹配的元素
+    pub fn select<'a>(&self, nodes: &'a [HtmlNode]) -> Vec<&'a HtmlNode> {
+        let mut found = Vec::new();
+        walk(nodes, &mut Vec::new(), &mut |chain| {
+            if self.matches_chain(chain) {
+                found.push(chain[chain.len() - 1].node);
+            }
+            true
+        });
+        found
+    }
+
+    fn matches_chain(&self, chain: &[Ctx<'_>]) -> bool {
+        self.alternatives
+            .iter()
+            .any(|complex| complex.matches(chain))
+    }
+}
+
+// 深度优先遍历元素；visit 返回 false 时不再进入该元素的子节点
+fn walk<'a, F>(nodes: &'a [HtmlNode], chain: &mut Vec<Ctx<'a>>, visit: &mut F)
+where
+    F: FnMut(&[Ctx<'a>]) -> bool,
+{
+    let sibling_count = nodes
+        .iter()
+        .filter(|node| matches!(node, HtmlNode::Element { .. }))
+        .count();
+    let mut index = 0;
+    for node in nodes {
+        let HtmlNode::Element { children, .. } = node else {
+            continue;
+        };
+        index += 1;
+        chain.push(Ctx {
+            node,
+            index,
+            sibling_count,
+        });
+        if visit(chain) {
+            walk(children, chain, visit);
+        }
+        chain.pop();
+    }
+}
+
+// 重建节点树，丢弃匹配任一选择器的元素及其子树
+fn remove_matching(nodes: &[HtmlNode], selectors: &[Selector]) -> Vec<HtmlNode> {
+    fn rebuild<'a>(
+        nodes: &'a [HtmlNode],
+        selectors: &[Selector],
+        chain: &mut Vec<Ctx<'a>>,
+    ) -> Vec<HtmlNode> {
+        let sibling_count = nodes
+            .iter()
+            .filter(|node| matches!(node, HtmlNode::Element { .. }))
+            .count();
+        let mut index = 0;
+        let mut output = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            let HtmlNode::Element {
+                tag,
+                attrs,
+                children,
+            } = node
+            else {
+                output.push(node.clone());
+                continue;
+            };
+            index += 1;
+            chain.push(Ctx {
+                node,
+                index,
+                sibling_count,
+            });
+            if !selectors
+                .iter()
+                .any(|selector| selector.matches_chain(chain))
+            {
+                output.push(HtmlNode::Element {
+                    tag: tag.clone(),
+                    attrs: attrs.clone(),
+                    children: rebuild(children, selectors, chain),
+                });
+            }
+            chain.pop();
+        }
+        output
+    }
+
+    rebuild(nodes, selectors, &mut Vec::new())
+}
+
+impl Complex {
+    // 从链尾元素开始由右向左匹配。后代组合符需要回溯尝试不同的祖先，
+    // 按（祖先链长度, 复合选择器序号）记录结果，避免 "a b c d" 这类选择器在深层 DOM 上指数级回溯
+    fn matches(&self, chain: &[Ctx<'_>]) -> bool {
+        let mut memo = vec![None; chain.len() * self.compounds.len()];
+        self.matches_at(chain, chain.len(), self.compounds.len() - 1, &mut memo)
+    }
+
+    // chain[..end] 的最后一个元素是否匹配 compounds[..=compound_idx]
+    fn matches_at(
+        &self,
+        chain: &[Ctx<'_>],
+        end: usize,
+        compound_idx: usize,
+        memo: &mut [Option<bool>],
+    ) -> bool {
+        if end == 0 {
+            return false;
+        }
+        let key = (end - 1) * self.compounds.len() + compound_idx;
+        if let Some(result) = memo[key] {
+            return result;
+        }
+
+        let result = self.compounds[compound_idx].matches(&chain[end - 1])
+            && (compound_idx == 0
+                || match self.combinators[compound_idx - 1] {
+                    Combinator::Child => self.matches_at(chain, end - 1, compound_idx - 1, memo),
+                    Combinator::Descendant => (1..end)
+                        .rev()
+                        .any(|ancestor| self.matches_at(chain, ancestor, compound_idx - 1, memo)),
+                });
+        memo[key] = Some(result);
+        result
+    }
+}
+
+impl Compound {
+    fn matches(&self, ctx: &Ctx<'_>) -> bool {
+        let HtmlNode::Element { tag, attrs, .. } = ctx.node else {
+            return false;
+        };
+        if let Some(expected) = &self.tag
+            && expected != tag
+        {
+            return false;
+        }
+        self.conditions
+            .iter()
+            .all(|condition| condition.matches(attrs, ctx))
+    }
+}
+
+impl Condition {
+    fn matches(&self, attrs: &[Attr], ctx: &Ctx<'_>) -> bool {
+        let attr = |name: &str| parser::attr(attrs, name);
+        match self {
+            Condition::Id(id) => attr("id") == Some(id.as_str()),
+            Condition::Class(class) => attr("class")
+                .is_some_and(|value| value.split_whitespace().any(|token| token == class)),
+            Condition::Attr { name, op } => {
+                let Some(value) = attr(name) else {
+                    return false;
+                };
+                match op {
+                    None => true,
+                    Some((AttrOp::Equals, expected)) => value == expected,
+                    Some((AttrOp::Includes, expected)) => {
+                        value.split_whitespace().any(|token| token == expected)
+                    }
+                    Some((AttrOp::DashMatch, expected)) => {
+                        value == expected || value.starts_with(&format!("{expected}-"))
+                    }
+                    Some((AttrOp::Prefix, expected)) => {
+                        !expected.is_empty() && value.starts_with(expected.as_str())
+                    }
+                    Some((AttrOp::Suffix, expected)) => {
+                        !expected.is_empty() && value.ends_with(expected.as_str())
+                    }
+                    Some((AttrOp::Substring, expected)) => {
+                        !expected.is_empty() && value.contains(expected.as_str())
+                    }
+                }
+            }
+            Condition::NthChild { a, b } => {
+                let index = ctx.index as i64;
+                if *a == 0 {
+                    index == *b
+                } else {
+                    let diff = index - b;
+                    diff % a == 0 && diff / a >= 0
+                }
+            }
+            Condition::LastChild => ctx.index == ctx.sibling_count,
+        }
+    }
+}
+
+// 按顶层逗号切分选择器列表（忽略括号与引号内的逗号）
+fn split_top_level(input: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+    for (idx, ch) in input.char_indices() {
+        match (quote, ch) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, '(' | '[') => depth += 1,
+            (None, ')' | ']') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                parts.push(&input[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&input[start..]);
+    parts
+}
+
+fn parse_complex(input: &str) -> Result<Complex, String> {
+    let mut chars = input.chars().peekable();
+    let mut compounds = Vec::new();
+    let mut combinators = Vec::new();
+
+    loop {
+        skip_whitespace(&mut chars);
+        compounds.push(parse_compound(&mut chars, input)?);
+
+        let had_space = skip_whitespace(&mut chars);
+        match chars.peek() {
+            None => break,
+            Some('>') => {
+                chars.next();
+                combinators.push(Combinator::Child);
+            }
+            Some('+' | '~') => {
+                return Err(format!("sibling combinators are not supported: {input}"));
+            }
+            Some(_) if had_space => combinators.push(Combinator::Descendant),
+            Some(ch) => return Err(format!("unexpected '{ch}' in selector: {input}")),
+        }
+    }
+
+    Ok(Complex {
+        compounds,
+        combinators,
+    })
+}
+
+fn parse_compound(chars: &mut Peekable<Chars<'_>>, input: &str) -> Result<Compound, String> {
+    let mut compound = Compound::default();
+    let mut universal = false;
+
+    match chars.peek() {
+        Some('*') => {
+            chars.next();
+            universal = true;
+        }
+        Some(ch) if is_ident_char(*ch) => {
+            compound.tag = Some(read_ident(chars).to_ascii_lowercase());
+        }
+        _ => {}
+    }
+
+    loop {
+        match chars.peek() {
+            Some('#') => {
+                chars.next();
+                let id = read_ident(chars);
+                if id.is_empty() {
+                    return Err(format!("expected id after '#': {input}"));
+                }
+                compound.conditions.push(Condition::Id(id));
+            }
+            Some('.') => {
+                chars.next();
+                let class = read_ident(chars);
+                if class.is_empty() {
+                    return Err(format!("expected class after '.': {input}"));
+                }
+                compound.conditions.push(Condition::Class(class));
+            }
+            Some('[') => {
+                chars.next();
+                compound.conditions.push(parse_attr(chars, input)?);
+            }
+            Some(':') => {
+                chars.next();
+                compound.conditions.push(parse_pseudo(chars, input)?);
+            }
+            _ => break,
+        }
+    }
+
+    if !universal && compound.tag.is_none() && compound.conditions.is_empty() {
+        return Err(match chars.peek() {
+            Some(ch) => format!("unexpected '{ch}' in selector: {input}"),
+            None => format!("selector ends with a combinator: {input}"),
+        });
+    }
+    Ok(compound)
+}
+
+fn parse_attr(chars: &mut Peekable<Chars<'_>>, input: &str) -> Result<Condition, String> {
+    skip_whitespace(chars);
+    let name = read_ident(chars).to_ascii_lowercase();
+    if name.is_empty() {
+        return Err(format!("expected attribute name: {input}"));
+    }
+    skip_whitespace(chars);
+
+    let op = match chars.next() {
+        Some(']') => return Ok(Condition::Attr { name, op: None }),
+        Some('=') => AttrOp::Equals,
+        Some(prefix @ ('~' | '|' | '^' | '$' | '*')) => {
+            if chars.next() != Some('=') {
+                return Err(format!("expected '=' after '{prefix}': {input}"));
+            }
+            match prefix {
+                '~' => AttrOp::Includes,
+                '|' => AttrOp::DashMatch,
+                '^' => AttrOp::Prefix,
+                '$' => AttrOp::Suffix,
+                _ => AttrOp::Substring,
+            }
+        }
+        _ => return Err(format!("malformed attribute selector: {input}")),
+    };
+
+    skip_whitespace(chars);
+    let value = match chars.peek() {
+        Some(quote @ ('"' | '\'')) => {
+            let quote = *quote;
+            chars.next();
+            let mut value = String::new();
+            loop {
+                match chars.next() {
+                    Some(ch) if ch == quote => break,
+                    Some(ch) => value.push(ch),
+                    None => return Err(format!("unterminated string: {input}")),
+                }
+            }
+            value
+        }
+        _ => read_ident(chars),
+    };
+    skip_whitespace(chars);
+    if chars.next() != Some(']') {
+        return Err(format!("expected ']': {input}"));
+    }
+
+    Ok(Condition::Attr {
+        name,
+        op: Some((op, value)),
+    })
+}
+
+fn parse_pseudo(chars: &mut Peekable<Chars<'_>>, input: &str) -> Result<Condition, String> {
+    let name = read_ident(chars).to_ascii_lowercase();
+    match name.as_str() {
+        "first-child" => Ok(Condition::NthChild { a: 0, b: 1 }),
+        "last-child" => Ok(Condition::LastChild),
+        "nth-child" => {
+            if chars.next() != Some('(') {
+                return Err(format!("expected '(' after :nth-child: {input}"));
+            }
+            let mut expr = String::new();
+            loop {
+                match chars.next() {
+                    Some(')') => break,
+                    Some(ch) => expr.push(ch),
+                    None => return Err(format!("unterminated :nth-child: {input}")),
+                }
+            }
+            let (a, b) = parse_nth(&expr)
+                .ok_or_else(|| format!("invalid :nth-child argument '{expr}': {input}"))?;
+            Ok(Condition::NthChild { a, b })
+        }
+        _ => Err(format!("unsupported pseudo-class :{name}: {input}")),
+    }
+}
+
+// 解析 an+b 表达式，支持 odd / even / n / 3 / 2n+1 / -n+3
+fn parse_nth(expr: &str) -> Option<(i64, i64)> {
+    let expr: String = expr
+        .chars()
+        .filter(|ch| !ch.is_whitespace())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    match expr.as_str() {
+        "odd" => return Some((2, 1)),
+        "even" => return Some((2, 0)),
+        _ => {}
+    }
+
+    let Some(n_pos) = expr.find('n') else {
+        return expr.parse::<i64>().ok().map(|b| (0, b));
+    };
+    let a = match &expr[..n_pos] {
+        "" | "+" => 1,
+        "-" => -1,
+        coefficient => coefficient.parse::<i64>().ok()?,
+    };
+    let rest = &expr[n_pos + 1..];
+    let b = if rest.is_empty() {
+        0
+    } else {
+        rest.strip_prefix('+').unwrap_or(rest).parse::<i64>().ok()?
+    };
+    Some((a, b))
+}
+
+fn is_ident_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '-' || ch == '_' || !ch.is_ascii()
+}
+
+fn read_ident(chars: &mut Peekable<Chars<'_>>) -> String {
+    let mut ident = String::new();
+    while let Some(ch) = chars.peek() {
+        if *ch == '\\' {
+            chars.next();
+            if let Some(escaped) = chars.next() {
+                ident.push(escaped);
+            }
+        } else if is_ident_char(*ch) {
+            ident.push(*ch);
+            chars.next();
+        } else {
+            break;
+        }
+    }
+    ident
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars<'_>>) -> bool {
+    let mut skipped = false;
+    while chars.peek().is_some_and(|ch| ch.is_whitespace()) {
+        chars.next();
+        skipped = true;
+    }
+    skipped
+}
+
+// cleanfetch 的 include_selectors / exclude_selectors：先移除排除的子树，
+// 再只保留包含选择器匹配到的最外层子树（按文档顺序）
+#[derive(Debug, Clone, Default)]
+pub struct ContentScope {
+    include: Vec<Selector>,
+    exclude: Vec<Selector>,
+}
+
+impl ContentScope {
+    pub fn parse(include: &[String], exclude: &[String]) -> Result<Self, String> {
+        let parse_all = |selectors: &[String]| {
+            selectors
+                .iter()
+                .map(|selector| Selector::parse(selector))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Self {
+            include: parse_all(include)?,
+            exclude: parse_all(exclude)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn apply(&self, nodes: Vec<HtmlNode>) -> Vec<HtmlNode> {
+        if self.is_empty() {
+            return nodes;
+        }
+
+        let nodes = if self.exclude.is_empty() {
+            nodes
+        } else {
+            remove_matching(&nodes, &self.exclude)
+        };
+        if self.include.is_empty() {
+            return nodes;
+        }
+
+        let mut found = Vec::new();
+        walk(&nodes, &mut Vec::new(), &mut |chain| {
+            if self
+                .include
+                .iter()
+                .any(|selector| selector.matches_chain(chain))
+            {
+                found.push(chain[chain.len() - 1].node.clone());
+                false
+            } else {
+                true
+            }
+        });
+        found
+    }
+}
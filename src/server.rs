@@ -1,5 +1,6 @@
-use std::env;
+use std::collections::BTreeMap;
 
+use reqwest::header::{HeaderName, HeaderValue};
 use rmcp::{
     ErrorData as McpError, ServerHandler,
     handler::server::{router::tool::ToolRouter, wrapper::Parameters},
@@ -8,13 +9,34 @@ use rmcp::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::{fetcher, html_to_markdown, html_to_text, html_to_urls_markdown, limit, markdown_to_image};
+use crate::{
+    HtmlConversion,
+    browser_action::{ActionOutcome, BrowserAction, InfiniteScroll, ScrollOutcome},
+    converter::{EscapeMode, MarkdownOptions},
+    crawl::{self, CrawlEdge, CrawlLimits, CrawlResult, CrawlScope, CrawledPage},
+    dom_filter,
+    extract::{ExtractField, ExtractPlan},
+    feed::{self, Feed},
+    fetch_error::{FetchError, FetchErrorKind},
+    fetcher::{self, FetchConfig, FetchOptions, FetchedDocument},
+    header_policy::HeaderPolicy,
+    html_to_markdown, html_to_markdown_document, html_to_tables, html_to_text_scoped,
+    html_to_urls_markdown_scoped, json_to_markdown, limit, markdown_to_image, pagination, parser,
+    pdf::{self, PdfDocument},
+    pretty_json, pretty_xml, section,
+    selector::ContentScope,
+    sitemap::{self, SitemapFilter},
+    table::Table,
+    text_to_urls_markdown,
+    toc::{self, Heading, TocEntry},
+    xml_to_markdown,
+};
 
 #[derive(Debug, Clone)]
 pub struct FetchServer {
     tool_router: ToolRouter<Self>,
-    selenium_url: String,
-    proxy_url: Option<String>,
+    fetch_config: FetchConfig,
+    header_policy: HeaderPolicy,
 }
 
 #[derive(Debug, Clone, Deserialize, schemars::JsonSchema)]
@@ -24,6 +46,8 @@ pub enum FetchKind {
     Text,
     Urls,
     Html,
+    Feed,
+    Tables,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
@@ -42,14 +66,208 @@ pub struct MarkdownToImageParams {
 pub struct CleanFetchParams {
     #[schemars(description = "要抓取的 URL 列表，至少一个")]
     pub urls: Vec<String>,
-    #[schemars(description = "返回类型：markdown | text | urls | html")]
+    #[schemars(description = "返回类型：markdown | text | urls | html | feed | tables")]
     pub kind: FetchKind,
+    #[serde(default)]
+    #[schemars(
+        description = "可选：附加的请求头，例如 Accept-Language、Authorization（敏感请求头受服务端策略限制）"
+    )]
+    pub headers: Option<BTreeMap<String, String>>,
+    #[serde(default)]
+    #[schemars(description = "可选：自定义 User-Agent")]
+    pub user_agent: Option<String>,
+    #[serde(default)]
+    #[schemars(description = "可选：要携带的 Cookie，键为名称、值为内容（受服务端策略限制）")]
+    pub cookies: Option<BTreeMap<String, String>>,
+    #[serde(default)]
+    #[schemars(
+        description = "可选：自动跟随“下一页”链接，最多合并 N 页（含首页，上限 20）为一个文档"
+    )]
+    pub follow_pagination: Option<usize>,
+    #[serde(default)]
+    #[schemars(
+        description = "可选：页面加载后在浏览器中依次执行的动作（点击、输入、按键、滚动、等待），设置后总是使用浏览器抓取"
+    )]
+    pub actions: Option<Vec<BrowserAction>>,
+    #[serde(default)]
+    #[schemars(
+        description = "可选：无限滚动模式，反复滚动到底部直到内容不再增长或达到上限（传 {} 使用默认值），设置后总是使用浏览器抓取"
+    )]
+    pub infinite_scroll: Option<InfiniteScroll>,
+    #[serde(default)]
+    #[schemars(
+        description = "可选：使用浏览器抓取时自动点击 Cookie 同意弹窗（OneTrust、Quantcast、Didomi、Cookiebot 及通用“全部接受”按钮），默认 false"
+    )]
+    pub dismiss_consent: Option<bool>,
+    #[serde(default)]
+    #[schemars(description = "可选：使用浏览器抓取时把同源 iframe 的内容内联到页面中，默认 false")]
+    pub inline_iframes: Option<bool>,
+    #[serde(default)]
+    #[schemars(
+        description = "可选：使用浏览器抓取时按计算样式标记不可见元素，转换时一并移除，默认 false"
+    )]
+    pub computed_visibility: Option<bool>,
+    #[serde(default)]
+    #[schemars(
+        description = "可选：转换为 markdown / text / tables 前移除 Cookie 同意弹窗与固定定位的弹窗类浮层，默认 true"
+    )]
+    pub drop_overlays: Option<bool>,
+    #[serde(default)]
+    #[schemars(
+        description = "可选：CSS 选择器列表，只转换匹配元素的子树（按文档顺序拼接），对 markdown / text / urls / tables 生效，kind 为 html 时不支持；支持类型、#id、.class、属性、后代与子代组合符、:nth-child"
+    )]
+    pub include_selectors: Option<Vec<String>>,
+    #[serde(default)]
+    #[schemars(
+        description = "可选：CSS 选择器列表，转换前移除匹配元素的子树，例如导航栏、侧边栏、评论区；kind 为 html 时不支持"
+    )]
+    pub exclude_selectors: Option<Vec<String>>,
+    #[serde(default)]
+    #[schemars(description = "可选：kind 为 tables 时为每个表格附带 CSV 文本，默认 false")]
+    pub csv: Option<bool>,
+    #[serde(default)]
+    #[schemars(
+        description = "可选：Markdown 特殊字符的转义程度：minimal（默认，只转义会被误解析为标记的字符）| strict（转义所有特殊字符）"
+    )]
+    pub markdown_escape: Option<EscapeMode>,
+    #[serde(default)]
+    #[schemars(
+        description = "可选：kind 为 markdown 时在标题后追加 {#id} 锚点（使用页面中的 id，没有时由标题文字生成），默认 false"
+    )]
+    pub heading_anchors: Option<bool>,
+    #[serde(default)]
+    #[schemars(
+        description = "可选：kind 为 markdown 时附带按级别嵌套的目录（标题级别、文字与锚点），默认 false；内容超出长度限制被省略时目录仍会返回"
+    )]
+    pub toc: Option<bool>,
+    #[serde(default)]
+    #[schemars(
+        description = "可选：只返回某个章节：从匹配的标题开始到下一个同级或更高级标题之前的内容，按锚点 id 或标题文字（忽略大小写）匹配，对 HTML 页面的 markdown / text / urls / tables 及 Markdown 文档生效，其他类型返回 unsupported_content_type 错误；找不到时返回 section_not_found 错误并列出页面中的标题；kind 为 html 时不支持"
+    )]
+    pub section: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct SitemapParams {
+    #[schemars(
+        description = "站点地址或站点地图地址（.xml / .xml.gz）；传入站点地址时从 robots.txt 或 /sitemap.xml 查找"
+    )]
+    pub url: String,
+    #[serde(default)]
+    #[schemars(description = "可选：只返回以该前缀开头的 URL")]
+    pub prefix: Option<String>,
+    #[serde(default)]
+    #[schemars(
+        description = "可选：只返回 lastmod 不早于该时间的 URL，格式如 2024-01-01 或 2024-01-01T00:00:00Z"
+    )]
+    pub modified_since: Option<String>,
+    #[serde(default)]
+    #[schemars(description = "可选：最多返回的 URL 数量，默认 1000")]
+    pub max_urls: Option<usize>,
+    #[serde(default)]
+    #[schemars(description = "可选：附加的请求头（敏感请求头受服务端策略限制）")]
+    pub headers: Option<BTreeMap<String, String>>,
+    #[serde(default)]
+    #[schemars(description = "可选：自定义 User-Agent")]
+    pub user_agent: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct CrawlParams {
+    #[schemars(description = "起始 URL 列表，至少一个")]
+    pub urls: Vec<String>,
+    #[serde(default)]
+    #[schemars(description = "可选：最大链接深度，起始页为 0，默认 2")]
+    pub max_depth: Option<usize>,
+    #[serde(default)]
+    #[schemars(description = "可选：最多抓取的页面数（含起始页），默认 20，上限 100")]
+    pub max_pages: Option<usize>,
+    #[serde(default)]
+    #[schemars(description = "可选：是否只跟随与起始页同主机的链接，默认 true")]
+    pub same_host: Option<bool>,
+    #[serde(default)]
+    #[schemars(description = "可选：只跟随路径以该前缀开头的链接，例如 /docs/")]
+    pub path_prefix: Option<String>,
+    #[serde(default)]
+    #[schemars(description = "可选：只跟随完整地址匹配该正则表达式的链接")]
+    pub url_pattern: Option<String>,
+    #[serde(default)]
+    #[schemars(description = "可选：附加的请求头（敏感请求头受服务端策略限制）")]
+    pub headers: Option<BTreeMap<String, String>>,
+    #[serde(default)]
+    #[schemars(description = "可选：自定义 User-Agent")]
+    pub user_agent: Option<String>,
+    #[serde(default)]
+    #[schemars(description = "可选：要携带的 Cookie（受服务端策略限制）")]
+    pub cookies: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ExtractParams {
+    #[schemars(description = "要抓取的页面 URL")]
+    pub url: String,
+    #[serde(default)]
+    #[schemars(
+        description = "可选：记录根选择器，每个匹配元素生成一条记录并返回数组；省略时整页生成一条记录"
+    )]
+    pub root: Option<String>,
+    #[schemars(
+        description = "字段映射：键为输出字段名，值为 CSS 选择器字符串，或 { selector, attr, list, fields } 形式的字段定义"
+    )]
+    pub fields: BTreeMap<String, ExtractField>,
+    #[serde(default)]
+    #[schemars(description = "可选：附加的请求头（敏感请求头受服务端策略限制）")]
+    pub headers: Option<BTreeMap<String, String>>,
+    #[serde(default)]
+    #[schemars(description = "可选：自定义 User-Agent")]
+    pub user_agent: Option<String>,
+    #[serde(default)]
+    #[schemars(description = "可选：要携带的 Cookie（受服务端策略限制）")]
+    pub cookies: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct ExtractResponse {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<FetchErrorKind>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct CrawlPageItem {
+    pub url: String,
+    pub depth: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub markdown: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<FetchErrorKind>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct CrawlResponse {
+    pub pages: Vec<CrawlPageItem>,
+    pub graph: Vec<CrawlEdge>,
+    pub truncated: bool,
 }
 
 #[derive(Debug, Serialize, schemars::JsonSchema)]
 pub struct CleanFetchItem {
     pub url: String,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub markdown: Option<String>,
@@ -60,7 +278,21 @@ pub struct CleanFetchItem {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub html: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed: Option<Feed>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tables: Option<Vec<Table>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub toc: Option<Vec<TocEntry>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actions: Option<Vec<ActionOutcome>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scroll: Option<ScrollOutcome>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<FetchErrorKind>,
 }
 
 /*
@@ -104,48 +336,245 @@ struct HtmlResult {
 #[tool_router]
 impl FetchServer {
     pub fn new(selenium_url: String) -> Self {
-        let proxy_url = env::var("PROXY_URL")
-            .ok()
-            .map(|v| v.trim().to_string())
-            .filter(|v| !v.is_empty());
-
         Self {
             tool_router: Self::tool_router(),
-            selenium_url,
-            proxy_url,
+            fetch_config: FetchConfig::from_env(selenium_url),
+            header_policy: HeaderPolicy::from_env(),
         }
     }
 
     #[tool(
-        description = "可以同时抓取多个 url，并且返回抓取结果。kind 参数控制返回内容类型：markdown（转换后的 Markdown 文本），text（纯文本），urls（提取的页面链接列表），html（原始 HTML）。"
+        description = "可以同时抓取多个 url，并且返回抓取结果。kind 参数控制返回内容类型：markdown（转换后的 Markdown 文本），text（纯文本），urls（提取的页面链接列表），html（原始 HTML），feed（RSS/Atom 订阅条目，传入网页地址时会自动发现其订阅源），tables（页面中所有表格的表头与数据行，可附带 CSV）。"
     )]
     async fn cleanfetch(
         &self,
-        Parameters(CleanFetchParams { urls, kind }): Parameters<CleanFetchParams>,
+        Parameters(CleanFetchParams {
+            urls,
+            kind,
+            headers,
+            user_agent,
+            cookies,
+            follow_pagination,
+            actions,
+            infinite_scroll,
+            dismiss_consent,
+            inline_iframes,
+            computed_visibility,
+            drop_overlays,
+            include_selectors,
+            exclude_selectors,
+            csv,
+            markdown_escape,
+            heading_anchors,
+            toc,
+            section,
+        }): Parameters<CleanFetchParams>,
     ) -> Result<CallToolResult, McpError> {
         if urls.is_empty() {
             return Ok(text_result_json("[]".to_string()));
         }
 
-        let htmls =
-            fetcher::fetch_html_batch(&self.selenium_url, &urls, self.proxy_url.as_deref()).await;
+        let scope = ContentScope::parse(
+            &include_selectors.unwrap_or_default(),
+            &exclude_selectors.unwrap_or_default(),
+        )
+        .map_err(|e| McpError::invalid_params(format!("invalid selector: {e}"), None))?;
+        let section = section
+            .map(|section| section.trim().to_string())
+            .filter(|section| !section.is_empty());
+        // html 返回页面源码本身，选择器与章节无法作用于其上
+        if matches!(kind, FetchKind::Html) && (section.is_some() || !scope.is_empty()) {
+            return Err(McpError::invalid_params(
+                "section, include_selectors and exclude_selectors are not supported with kind html",
+                None,
+            ));
+        }
+        let conversion = HtmlConversion {
+            scope,
+            section,
+            drop_overlays: drop_overlays.unwrap_or(true),
+            markdown: MarkdownOptions {
+                escape: markdown_escape.unwrap_or_default(),
+                heading_anchors: heading_anchors.unwrap_or(false),
+            },
+        };
+
+        let max_pages = follow_pagination
+            .unwrap_or(1)
+            .clamp(1, pagination::MAX_PAGES);
+        let mut options = build_fetch_options(headers, user_agent, cookies)?;
+        options.actions = actions.unwrap_or_default();
+        options.infinite_scroll = infinite_scroll;
+        options.dismiss_consent = dismiss_consent.unwrap_or(false);
+        options.inline_iframes = inline_iframes.unwrap_or(false);
+        options.computed_visibility = computed_visibility.unwrap_or(false);
+
+        let mut action_outcomes: Vec<Option<Vec<ActionOutcome>>> = vec![None; urls.len()];
+        let mut scroll_outcomes: Vec<Option<ScrollOutcome>> = vec![None; urls.len()];
+        let documents = match self.header_policy.check(&options) {
+            Ok(()) if options.needs_browser() => {
+                fetcher::fetch_rendered_batch(&self.fetch_config, &urls, &options)
+                    .await
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, page)| {
+                        page.map(|page| {
+                            if !page.actions.is_empty() {
+                                action_outcomes[idx] = Some(page.actions);
+                            }
+                            scroll_outcomes[idx] = page.scroll;
+                            FetchedDocument::Html(page.html)
+                        })
+                    })
+                    .collect()
+            }
+            Ok(()) => fetcher::fetch_document_batch(&self.fetch_config, &urls, &options).await,
+            Err(e) => vec![Err(e); urls.len()],
+        };
 
         let mut datas: Vec<Option<String>> = vec![None; urls.len()];
         let mut errors: Vec<Option<String>> = vec![None; urls.len()];
+        let mut error_kinds: Vec<Option<FetchErrorKind>> = vec![None; urls.len()];
+        let mut page_counts: Vec<Option<u32>> = vec![None; urls.len()];
+        let mut content_types: Vec<Option<String>> = vec![None; urls.len()];
+        let mut feeds: Vec<Option<Feed>> = vec![None; urls.len()];
+        let mut tables: Vec<Option<Vec<Table>>> = vec![None; urls.len()];
+        let mut tocs: Vec<Option<Vec<TocEntry>>> = vec![None; urls.len()];
 
         let mut succ_texts: Vec<String> = Vec::new();
         let mut succ_index: Vec<usize> = Vec::new();
 
-        for (idx, item) in htmls.iter().enumerate() {
-            match item {
-                Ok(html) => {
-                    let data = match kind {
-                        FetchKind::Markdown => html_to_markdown(html),
-                        FetchKind::Text => html_to_text(html),
-                        FetchKind::Urls => html_to_urls_markdown(html, &urls[idx]),
-                        FetchKind::Html => html.clone(),
-                    };
+        for (idx, item) in documents.into_iter().enumerate() {
+            if let Ok(doc) = &item {
+                content_types[idx] = Some(doc.content_type().to_string());
+            }
 
+            if matches!(kind, FetchKind::Feed) {
+                match self.resolve_feed(item, &urls[idx], &options).await {
+                    Ok((feed, content_type)) => {
+                        // 从网页发现的订阅源以订阅文档本身的类型为准
+                        content_types[idx] = Some(content_type.to_string());
+                        feeds[idx] = Some(feed);
+                    }
+                    Err(e) => {
+                        errors[idx] = Some(e.to_string());
+                        error_kinds[idx] = Some(e.kind());
+                    }
+                }
+                continue;
+            }
+
+            if matches!(kind, FetchKind::Tables) {
+                match item {
+                    Ok(FetchedDocument::Html(html)) => {
+                        let pages = self
+                            .collect_pages(html, &urls[idx], &options, max_pages)
+                            .await;
+                        if pages.len() > 1 {
+                            page_counts[idx] = Some(pages.len() as u32);
+                        }
+                        match join_tables(pages, &conversion, csv.unwrap_or(false)) {
+                            Ok(found) => tables[idx] = Some(found),
+                            Err(e) => {
+                                errors[idx] = Some(e.to_string());
+                                error_kinds[idx] = Some(e.kind());
+                            }
+                        }
+                    }
+                    Ok(other) => {
+                        let e = FetchError::UnsupportedContentType(format!(
+                            "{} (tables kind requires an html page)",
+                            other.content_type()
+                        ));
+                        errors[idx] = Some(e.to_string());
+                        error_kinds[idx] = Some(e.kind());
+                    }
+                    Err(e) => {
+                        errors[idx] = Some(e.to_string());
+                        error_kinds[idx] = Some(e.kind());
+                    }
+                }
+                continue;
+            }
+
+            let converted = match item {
+                // section 只能作用于有标题结构的文档：HTML 按 DOM 截取，Markdown 按 ATX 标题截取
+                Ok(FetchedDocument::Markdown(text))
+                    if let Some(query) = conversion.section.as_deref() =>
+                {
+                    section::extract_markdown_section(&text, query)
+                        .map_err(FetchError::SectionNotFound)
+                        .map(|text| match kind {
+                            FetchKind::Urls => text_to_urls_markdown(&text),
+                            _ => text,
+                        })
+                }
+                Ok(other)
+                    if conversion.section.is_some()
+                        && !matches!(other, FetchedDocument::Html(_)) =>
+                {
+                    Err(FetchError::UnsupportedContentType(format!(
+                        "{} (section requires an html or markdown document)",
+                        other.content_type()
+                    )))
+                }
+                Ok(FetchedDocument::Html(html)) => {
+                    let pages = self
+                        .collect_pages(html, &urls[idx], &options, max_pages)
+                        .await;
+                    if pages.len() > 1 {
+                        page_counts[idx] = Some(pages.len() as u32);
+                    }
+                    join_pages(&kind, pages, &conversion).map(|(data, headings)| {
+                        if toc.unwrap_or(false) && matches!(kind, FetchKind::Markdown) {
+                            tocs[idx] = Some(toc::build_toc(&headings));
+                        }
+                        data
+                    })
+                }
+                Ok(FetchedDocument::Pdf(bytes)) => match kind {
+                    FetchKind::Markdown | FetchKind::Text => extract_pdf(bytes).await.map(|doc| {
+                        page_counts[idx] = Some(doc.page_count());
+                        if matches!(kind, FetchKind::Markdown) {
+                            pdf::pdf_to_markdown(&doc)
+                        } else {
+                            pdf::pdf_to_text(&doc)
+                        }
+                    }),
+                    FetchKind::Urls | FetchKind::Html | FetchKind::Feed | FetchKind::Tables => {
+                        Err(FetchError::UnsupportedContentType(
+                            "application/pdf (only markdown and text kinds are supported)"
+                                .to_string(),
+                        ))
+                    }
+                },
+                Ok(FetchedDocument::PlainText(text)) | Ok(FetchedDocument::Markdown(text)) => {
+                    Ok(match kind {
+                        FetchKind::Urls => text_to_urls_markdown(&text),
+                        FetchKind::Markdown
+                        | FetchKind::Text
+                        | FetchKind::Html
+                        | FetchKind::Feed
+                        | FetchKind::Tables => text,
+                    })
+                }
+                Ok(FetchedDocument::Json(json)) => Ok(match kind {
+                    FetchKind::Markdown => json_to_markdown(&json),
+                    FetchKind::Text => pretty_json(&json),
+                    FetchKind::Urls => text_to_urls_markdown(&json),
+                    FetchKind::Html | FetchKind::Feed | FetchKind::Tables => json,
+                }),
+                Ok(FetchedDocument::Xml(xml)) => Ok(match kind {
+                    FetchKind::Markdown => xml_to_markdown(&xml),
+                    FetchKind::Text => pretty_xml(&xml),
+                    FetchKind::Urls => text_to_urls_markdown(&xml),
+                    FetchKind::Html | FetchKind::Feed | FetchKind::Tables => xml,
+                }),
+                Err(e) => Err(e),
+            };
+
+            match converted {
+                Ok(data) => {
                     if matches!(kind, FetchKind::Markdown | FetchKind::Text) {
                         succ_texts.push(data.clone());
                         succ_index.push(idx);
@@ -154,7 +583,8 @@ impl FetchServer {
                     datas[idx] = Some(data);
                 }
                 Err(e) => {
-                    errors[idx] = Some(e.clone());
+                    errors[idx] = Some(e.to_string());
+                    error_kinds[idx] = Some(e.kind());
                 }
             }
         }
@@ -170,6 +600,7 @@ impl FetchServer {
                             .clone()
                             .unwrap_or_else(|| limit::ERROR_MESSAGE.to_string()),
                     );
+                    error_kinds[idx] = Some(FetchErrorKind::LimitExceeded);
                 }
             }
         }
@@ -198,12 +629,20 @@ impl FetchServer {
 
                 CleanFetchItem {
                     url: url.clone(),
+                    content_type: content_types[idx].clone(),
                     data,
                     markdown,
                     text,
                     urls_markdown,
                     html,
+                    feed: feeds[idx].clone(),
+                    tables: tables[idx].clone(),
+                    toc: tocs[idx].clone(),
+                    page_count: page_counts[idx],
+                    actions: action_outcomes[idx].clone(),
+                    scroll: scroll_outcomes[idx].clone(),
                     error: errors[idx].clone(),
+                    error_kind: error_kinds[idx],
                 }
             })
             .collect();
@@ -211,14 +650,317 @@ impl FetchServer {
         Ok(text_result_json(to_json(payload)))
     }
 
+    // 从首页开始依次跟随下一页链接，遇到非 HTML、抓取失败或重复地址时停止
+    async fn collect_pages(
+        &self,
+        first_html: String,
+        url: &str,
+        options: &FetchOptions,
+        max_pages: usize,
+    ) -> Vec<(String, String)> {
+        // 动作、滚动与同意弹窗只在首页执行，后续页面按普通抓取处理，避免重复执行且结果无处报告
+        let follow_options = FetchOptions {
+            actions: Vec::new(),
+            infinite_scroll: None,
+            dismiss_consent: false,
+            ..options.clone()
+        };
+        let mut pages = vec![(url.to_string(), first_html)];
+        while pages.len() < max_pages {
+            let (page_url, html) = &pages[pages.len() - 1];
+            let dom = parser::parse_html(html);
+            let Some(next_url) = pagination::find_next_page(&dom, page_url) else {
+                break;
+            };
+            if pages.iter().any(|(seen, _)| *seen == next_url) {
+                break;
+            }
+
+            match fetcher::fetch_document(&self.fetch_config, &next_url, &follow_options).await {
+                Ok(FetchedDocument::Html(next_html)) => pages.push((next_url, next_html)),
+                Ok(other) => {
+                    tracing::debug!(
+                        "stop pagination at {next_url}: got {}",
+                        other.content_type()
+                    );
+                    break;
+                }
+                Err(e) => {
+                    tracing::debug!("stop pagination at {next_url}: {e}");
+                    break;
+                }
+            }
+        }
+        pages
+    }
+
+    async fn resolve_feed(
+        &self,
+        item: Result<FetchedDocument, FetchError>,
+        url: &str,
+        options: &FetchOptions,
+    ) -> Result<(Feed, &'static str), FetchError> {
+        let doc = item?;
+        let content_type = doc.content_type();
+        match doc {
+            FetchedDocument::Xml(xml) => feed::parse_feed(&xml, url)
+                .map(|feed| (feed, content_type))
+                .map_err(FetchError::Parse),
+            FetchedDocument::Html(html) => {
+                let dom = parser::parse_html(&html);
+                let feed_url = feed::discover_feed_urls(&dom, url)
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| {
+                        FetchError::Parse("no rss or atom feed found in page".to_string())
+                    })?;
+
+                let feed_doc =
+                    fetcher::fetch_document(&self.fetch_config, &feed_url, options).await?;
+                let feed_content_type = feed_doc.content_type();
+                match feed_doc {
+                    FetchedDocument::Xml(xml) => feed::parse_feed(&xml, &feed_url)
+                        .map(|feed| (feed, feed_content_type))
+                        .map_err(FetchError::Parse),
+                    other => Err(FetchError::UnsupportedContentType(format!(
+                        "{} (discovered feed {feed_url} is not xml)",
+                        other.content_type()
+                    ))),
+                }
+            }
+            other => Err(FetchError::UnsupportedContentType(format!(
+                "{} (feed kind requires an rss/atom feed or an html page linking to one)",
+                other.content_type()
+            ))),
+        }
+    }
+
+    #[tool(
+        description = "查找并展开站点地图：支持 robots.txt 中声明的站点地图、/sitemap.xml、站点地图索引递归及 gzip 压缩，返回 URL 及其 lastmod / priority，可按前缀和修改时间过滤。"
+    )]
+    async fn sitemap(
+        &self,
+        Parameters(SitemapParams {
+            url,
+            prefix,
+            modified_since,
+            max_urls,
+            headers,
+            user_agent,
+        }): Parameters<SitemapParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let modified_since = match modified_since
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+        {
+            Some(since) => Some(sitemap::parse_w3c_datetime(since).ok_or_else(|| {
+                McpError::invalid_params(format!("invalid modified_since: {since}"), None)
+            })?),
+            None => None,
+        };
+
+        let filter = SitemapFilter {
+            prefix: prefix.filter(|v| !v.is_empty()),
+            modified_since,
+            max_urls: max_urls.unwrap_or(sitemap::DEFAULT_MAX_URLS),
+        };
+
+        let options = build_fetch_options(headers, user_agent, None)?;
+        let roots = match self.header_policy.check(&options) {
+            Ok(()) => sitemap::discover_sitemaps(&self.fetch_config, &options, &url).await,
+            Err(e) => Err(e),
+        };
+
+        let payload = match roots {
+            Ok(roots) => to_json(
+                sitemap::expand_sitemaps(&self.fetch_config, &options, roots, &filter).await,
+            ),
+            Err(e) => to_json(serde_json::json!({
+                "url": url,
+                "error": e.to_string(),
+                "error_kind": e.kind(),
+            })),
+        };
+
+        Ok(text_result_json(payload))
+    }
+
+    #[tool(
+        description = "从起始 URL 出发按广度优先抓取站点页面：跟随页面中 a / area 元素的链接（可限定同主机、路径前缀、正则），受最大深度与最大页数限制，返回每个页面的 Markdown 以及页面之间的链接图。"
+    )]
+    async fn crawl(
+        &self,
+        Parameters(CrawlParams {
+            urls,
+            max_depth,
+            max_pages,
+            same_host,
+            path_prefix,
+            url_pattern,
+            headers,
+            user_agent,
+            cookies,
+        }): Parameters<CrawlParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if urls.is_empty() {
+            return Err(McpError::invalid_params("urls must not be empty", None));
+        }
+
+        let pattern = url_pattern
+            .filter(|v| !v.trim().is_empty())
+            .map(|v| {
+                regex::Regex::new(&v).map_err(|e| {
+                    McpError::invalid_params(format!("invalid url_pattern: {e}"), None)
+                })
+            })
+            .transpose()?;
+        let scope = CrawlScope {
+            same_host: same_host.unwrap_or(true),
+            path_prefix: path_prefix.filter(|v| !v.is_empty()),
+            pattern,
+        };
+        let limits = CrawlLimits {
+            max_depth: max_depth.unwrap_or(crawl::DEFAULT_MAX_DEPTH),
+            max_pages: max_pages
+                .unwrap_or(crawl::DEFAULT_MAX_PAGES)
+                .clamp(1, crawl::MAX_PAGES_LIMIT),
+        };
+
+        let options = build_fetch_options(headers, user_agent, cookies)?;
+        // 与其他工具一致，策略拒绝作为每个起始地址的错误返回
+        let result = match self.header_policy.check(&options) {
+            Ok(()) => crawl::crawl(&self.fetch_config, &options, &urls, &scope, limits).await,
+            Err(e) => CrawlResult {
+                pages: urls
+                    .iter()
+                    .map(|url| CrawledPage {
+                        url: url.clone(),
+                        depth: 0,
+                        document: Err(e.clone()),
+                    })
+                    .collect(),
+                ..Default::default()
+            },
+        };
+
+        let mut pages: Vec<CrawlPageItem> = Vec::with_capacity(result.pages.len());
+        for page in result.pages {
+            let content_type = page
+                .document
+                .as_ref()
+                .ok()
+                .map(|doc| doc.content_type().to_string());
+            let mut item = CrawlPageItem {
+                url: page.url,
+                depth: page.depth,
+                content_type,
+                markdown: None,
+                page_count: None,
+                error: None,
+                error_kind: None,
+            };
+            match document_to_markdown(page.document).await {
+                Ok((markdown, page_count)) => {
+                    item.markdown = Some(markdown);
+                    item.page_count = page_count;
+                }
+                Err(e) => {
+                    item.error = Some(e.to_string());
+                    item.error_kind = Some(e.kind());
+                }
+            }
+            pages.push(item);
+        }
+
+        let succ_index: Vec<usize> = (0..pages.len())
+            .filter(|idx| pages[*idx].markdown.is_some())
+            .collect();
+        let succ_texts: Vec<String> = succ_index
+            .iter()
+            .filter_map(|idx| pages[*idx].markdown.clone())
+            .collect();
+        for (pos, lim) in limit::limit_items(&succ_texts).iter().enumerate() {
+            if !lim.include {
+                let page = &mut pages[succ_index[pos]];
+                page.markdown = None;
+                page.error = Some(
+                    lim.error
+                        .clone()
+                        .unwrap_or_else(|| limit::ERROR_MESSAGE.to_string()),
+                );
+                page.error_kind = Some(FetchErrorKind::LimitExceeded);
+            }
+        }
+
+        Ok(text_result_json(to_json(CrawlResponse {
+            pages,
+            graph: result.edges,
+            truncated: result.truncated,
+        })))
+    }
+
+    #[tool(
+        description = "按声明式字段映射从页面中提取结构化数据：每个字段指定 CSS 选择器，读取文本或属性，可返回列表或嵌套对象；设置 root 时每个匹配元素生成一条记录。适合商品列表、版本发布表、职位列表等。"
+    )]
+    async fn extract(
+        &self,
+        Parameters(ExtractParams {
+            url,
+            root,
+            fields,
+            headers,
+            user_agent,
+            cookies,
+        }): Parameters<ExtractParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let plan = ExtractPlan::compile(root.as_deref(), &fields)
+            .map_err(|e| McpError::invalid_params(format!("invalid extract mapping: {e}"), None))?;
+
+        let options = build_fetch_options(headers, user_agent, cookies)?;
+        let document = match self.header_policy.check(&options) {
+            Ok(()) => fetcher::fetch_document(&self.fetch_config, &url, &options).await,
+            Err(e) => Err(e),
+        };
+
+        let mut response = ExtractResponse {
+            url: url.clone(),
+            content_type: document
+                .as_ref()
+                .ok()
+                .map(|doc| doc.content_type().to_string()),
+            data: None,
+            error: None,
+            error_kind: None,
+        };
+        let extracted = match document {
+            Ok(FetchedDocument::Html(html)) => Ok(plan.run(&parser::parse_html(&html), &url)),
+            Ok(other) => Err(FetchError::UnsupportedContentType(format!(
+                "{} (extract requires an html page)",
+                other.content_type()
+            ))),
+            Err(e) => Err(e),
+        };
+        match extracted {
+            Ok(data) => response.data = Some(data),
+            Err(e) => {
+                response.error = Some(e.to_string());
+                response.error_kind = Some(e.kind());
+            }
+        }
+
+        Ok(text_result_json(to_json(response)))
+    }
+
     #[tool(description = "将原始 HTML 转换为图片，返回 base64 编码的 PNG 图片")]
     async fn html_to_image(
         &self,
         Parameters(HtmlToImageParams { html }): Parameters<HtmlToImageParams>,
     ) -> Result<CallToolResult, McpError> {
-        let base64_data = crate::html_to_image::html_to_image(&self.selenium_url, &html)
-            .await
-            .map_err(|e| McpError::internal_error(e, None))?;
+        let base64_data =
+            crate::html_to_image::html_to_image(&self.fetch_config.selenium_url, &html)
+                .await
+                .map_err(|e| McpError::internal_error(e, None))?;
 
         Ok(CallToolResult::success(vec![Content::image(
             base64_data,
@@ -231,9 +973,10 @@ impl FetchServer {
         &self,
         Parameters(MarkdownToImageParams { markdown }): Parameters<MarkdownToImageParams>,
     ) -> Result<CallToolResult, McpError> {
-        let base64_data = markdown_to_image::markdown_to_image(&self.selenium_url, &markdown)
-            .await
-            .map_err(|e| McpError::internal_error(e, None))?;
+        let base64_data =
+            markdown_to_image::markdown_to_image(&self.fetch_config.selenium_url, &markdown)
+                .await
+                .map_err(|e| McpError::internal_error(e, None))?;
 
         Ok(CallToolResult::success(vec![Content::image(
             base64_data,
@@ -247,7 +990,7 @@ impl ServerHandler for FetchServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             instructions: Some(
-                "Fetch MCP server with unified tool: cleanfetch (kind: markdown | text | urls | html)"
+                "Fetch MCP server with tools: cleanfetch (kind: markdown | text | urls | html | feed | tables), sitemap, crawl, extract"
                     .to_string(),
             ),
             capabilities: ServerCapabilities::builder().enable_tools().build(),
@@ -256,6 +999,145 @@ impl ServerHandler for FetchServer {
     }
 }
 
+// PDF 解析为 CPU 密集型操作，且解析库遇到异常文件可能 panic，放到阻塞线程中执行
+async fn extract_pdf(bytes: Vec<u8>) -> Result<PdfDocument, FetchError> {
+    tokio::task::spawn_blocking(move || pdf::extract_pdf(&bytes))
+        .await
+        .map_err(|e| FetchError::Parse(format!("pdf extraction aborted: {e}")))?
+        .map_err(FetchError::Parse)
+}
+
+// 返回转换结果及其中的标题（仅 markdown 记录标题）
+fn convert_html(
+    kind: &FetchKind,
+    html: String,
+    url: &str,
+    conversion: &HtmlConversion,
+) -> Result<(String, Vec<Heading>), FetchError> {
+    match kind {
+        FetchKind::Markdown => html_to_markdown_document(&html, conversion)
+            .map(|document| (document.markdown, document.headings)),
+        FetchKind::Text => html_to_text_scoped(&html, conversion).map(|text| (text, Vec::new())),
+        FetchKind::Urls => {
+            html_to_urls_markdown_scoped(&html, url, conversion).map(|urls| (urls, Vec::new()))
+        }
+        FetchKind::Html => Ok((dom_filter::remove_hidden_markers(&html), Vec::new())),
+        FetchKind::Feed | FetchKind::Tables => Ok((html, Vec::new())),
+    }
+}
+
+// 多页表格按顺序合并，position 在所有页面中连续编号；指定 section 时跳过不含该章节的页面
+fn join_tables(
+    pages: Vec<(String, String)>,
+    conversion: &HtmlConversion,
+    with_csv: bool,
+) -> Result<Vec<Table>, FetchError> {
+    let mut tables: Vec<Table> = Vec::new();
+    let mut first_error = None;
+    let mut found = false;
+    for (_, html) in &pages {
+        match html_to_tables(html, conversion, with_csv) {
+            Ok(page_tables) => {
+                found = true;
+                tables.extend(page_tables);
+            }
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    if let Some(e) = first_error
+        && !found
+    {
+        return Err(e);
+    }
+    for (position, table) in tables.iter_mut().enumerate() {
+        table.position = position;
+    }
+    Ok(tables)
+}
+
+// 多页内容按顺序拼接，每页前插入与 PDF 相同风格的页码标记
+fn join_pages(
+    kind: &FetchKind,
+    pages: Vec<(String, String)>,
+    conversion: &HtmlConversion,
+) -> Result<(String, Vec<Heading>), FetchError> {
+    if pages.len() == 1 {
+        let (url, html) = pages.into_iter().next().unwrap_or_default();
+        return convert_html(kind, html, &url, conversion);
+    }
+
+    // 指定 section 时只保留包含该章节的页面，全部不含时返回第一个错误
+    let mut headings = Vec::new();
+    let mut parts = Vec::new();
+    let mut first_error = None;
+    for (pos, (url, html)) in pages.into_iter().enumerate() {
+        let marker = match kind {
+            FetchKind::Text => format!("--- page {}: {url} ---", pos + 1),
+            _ => format!("<!-- page {}: {url} -->", pos + 1),
+        };
+        match convert_html(kind, html, &url, conversion) {
+            Ok((content, page_headings)) => {
+                headings.extend(page_headings);
+                parts.push(format!("{marker}\n\n{content}"));
+            }
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    match first_error {
+        Some(e) if parts.is_empty() => Err(e),
+        _ => Ok((parts.join("\n\n"), headings)),
+    }
+}
+
+async fn document_to_markdown(
+    document: Result<FetchedDocument, FetchError>,
+) -> Result<(String, Option<u32>), FetchError> {
+    Ok(match document? {
+        FetchedDocument::Html(html) => (html_to_markdown(&html), None),
+        FetchedDocument::Pdf(bytes) => {
+            let doc = extract_pdf(bytes).await?;
+            (pdf::pdf_to_markdown(&doc), Some(doc.page_count()))
+        }
+        FetchedDocument::PlainText(text) | FetchedDocument::Markdown(text) => (text, None),
+        FetchedDocument::Json(json) => (json_to_markdown(&json), None),
+        FetchedDocument::Xml(xml) => (xml_to_markdown(&xml), None),
+    })
+}
+
+fn build_fetch_options(
+    headers: Option<BTreeMap<String, String>>,
+    user_agent: Option<String>,
+    cookies: Option<BTreeMap<String, String>>,
+) -> Result<FetchOptions, McpError> {
+    let headers: Vec<(String, String)> = headers.unwrap_or_default().into_iter().collect();
+    for (name, value) in &headers {
+        HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| McpError::invalid_params(format!("invalid header name: {name}"), None))?;
+        HeaderValue::from_str(value).map_err(|_| {
+            McpError::invalid_params(format!("invalid value for header: {name}"), None)
+        })?;
+    }
+
+    let user_agent = user_agent
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty());
+    if let Some(ua) = &user_agent {
+        HeaderValue::from_str(ua)
+            .map_err(|_| McpError::invalid_params("invalid user_agent", None))?;
+    }
+
+    Ok(FetchOptions {
+        headers,
+        user_agent,
+        cookies: cookies.unwrap_or_default().into_iter().collect(),
+        ..Default::default()
+    })
+}
+
 fn text_result_json(text: String) -> CallToolResult {
     CallToolResult::success(vec![Content::text(text)])
 }
@@ -0,0 +1,364 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    io::Read,
+};
+
+use flate2::read::GzDecoder;
+use rmcp::schemars;
+use serde::Serialize;
+use url::Url;
+
+use crate::{
+    content_type,
+    fetch_error::{FetchError, FetchErrorKind},
+    fetcher::{self, FetchConfig, FetchOptions, FetchedResponse},
+    urls::resolve_href,
+    xml::{self, XmlNode},
+};
+
+const MAX_SITEMAP_DEPTH: usize = 5;
+const MAX_SITEMAP_FILES: usize = 50;
+pub const DEFAULT_MAX_URLS: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct SitemapUrl {
+    pub loc: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lastmod: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changefreq: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct SitemapError {
+    pub sitemap_url: String,
+    pub error: String,
+    pub error_kind: FetchErrorKind,
+}
+
+#[derive(Debug, Clone, Default, Serialize, schemars::JsonSchema)]
+pub struct SitemapReport {
+    // 实际读取过的站点地图文件（含索引）
+    pub sitemaps: Vec<String>,
+    pub urls: Vec<SitemapUrl>,
+    // 因数量上限提前停止时为 true
+    pub truncated: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<SitemapError>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SitemapFilter {
+    pub prefix: Option<String>,
+    // 毫秒级 Unix 时间戳，由 parse_w3c_datetime 解析得到
+    pub modified_since: Option<i64>,
+    pub max_urls: usize,
+}
+
+impl Default for SitemapFilter {
+    fn default() -> Self {
+        Self {
+            prefix: None,
+            modified_since: None,
+            max_urls: DEFAULT_MAX_URLS,
+        }
+    }
+}
+
+impl SitemapFilter {
+    fn matches(&self, entry: &SitemapUrl) -> bool {
+        if let Some(prefix) = &self.prefix
+            && !entry.loc.starts_with(prefix.as_str())
+        {
+            return false;
+        }
+
+        // 缺少或无法解析 lastmod 的条目视为不满足时间条件
+        match self.modified_since {
+            None => true,
+            Some(since) => entry
+                .lastmod
+                .as_deref()
+                .and_then(parse_w3c_datetime)
+                .is_some_and(|lastmod| lastmod >= since),
+        }
+    }
+}
+
+// 解析 W3C Datetime（YYYY、YYYY-MM、YYYY-MM-DD、YYYY-MM-DDThh:mm[:ss[.s]]TZD），返回毫秒级 Unix 时间戳。
+// 只有日期时按当天 0 点 UTC 计算；缺少时区时按 UTC 处理
+pub fn parse_w3c_datetime(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let (date, time) = match value.split_once('T') {
+        Some((date, time)) => (date, Some(time)),
+        None => (value, None),
+    };
+
+    let mut parts = date.split('-');
+    let year = number(parts.next()?, 4)?;
+    let month = parts
+        .next()
+        .map(|part| number(part, 2))
+        .unwrap_or(Some(1))?;
+    let day = parts
+        .next()
+        .map(|part| number(part, 2))
+        .unwrap_or(Some(1))?;
+    if parts.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    if time.is_some() && date.len() != 10 {
+        return None;
+    }
+
+    let mut millis = days_from_civil(year, month, day) * 86_400_000;
+    if let Some(time) = time {
+        let (clock, offset) = split_zone(time)?;
+        let mut fields = clock.splitn(3, ':');
+        let hour = number(fields.next()?, 2)?;
+        let minute = number(fields.next()?, 2)?;
+        let (second, fraction) = match fields.next() {
+            Some(seconds) => match seconds.split_once('.') {
+                Some((whole, fraction)) => (number(whole, 2)?, fraction),
+                None => (number(seconds, 2)?, ""),
+            },
+            None => (0, ""),
+        };
+        if hour > 23
+            || minute > 59
+            || second > 60
+            || !fraction.chars().all(|ch| ch.is_ascii_digit())
+        {
+            return None;
+        }
+        let fraction_millis = format!("{fraction:0<3}")[..3].parse::<i64>().ok()?;
+        millis += ((hour * 60 + minute) * 60 + second) * 1000 + fraction_millis - offset * 60_000;
+    }
+    Some(millis)
+}
+
+// 把时间部分拆成时刻与时区偏移（分钟）；Z 或缺省为 UTC
+fn split_zone(time: &str) -> Option<(&str, i64)> {
+    if let Some(clock) = time.strip_suffix('Z') {
+        return Some((clock, 0));
+    }
+    match time.rfind(['+', '-']) {
+        Some(index) => {
+            let (clock, zone) = time.split_at(index);
+            let sign = if zone.starts_with('-') { -1 } else { 1 };
+            let (hours, minutes) = zone[1..].split_once(':')?;
+            Some((clock, sign * (number(hours, 2)? * 60 + number(minutes, 2)?)))
+        }
+        None => Some((time, 0)),
+    }
+}
+
+fn number(part: &str, len: usize) -> Option<i64> {
+    if part.len() != len || !part.chars().all(|ch| ch.is_ascii_digit()) {
+        return None;
+    }
+    part.parse().ok()
+}
+
+// 公历日期距 1970-01-01 的天数
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[derive(Debug, Clone)]
+pub enum SitemapDocument {
+    Index(Vec<String>),
+    UrlSet(Vec<SitemapUrl>),
+}
+
+pub fn parse_sitemap(input: &str, sitemap_url: &str) -> Result<SitemapDocument, String> {
+    let nodes = xml::parse_xml(input)?;
+    let root = xml::root_element(&nodes).ok_or_else(|| "empty xml document".to_string())?;
+
+    match root.local_name().as_deref() {
+        Some("sitemapindex") => Ok(SitemapDocument::Index(
+            root.child_elements("sitemap")
+                .filter_map(|sitemap| sitemap.child_text("loc"))
+                .filter_map(|loc| resolve_href(&loc, sitemap_url))
+                .collect(),
+        )),
+        Some("urlset") => Ok(SitemapDocument::UrlSet(
+            root.child_elements("url")
+                .filter_map(|url| parse_url_entry(url, sitemap_url))
+                .collect(),
+        )),
+        _ => Err("not a sitemap (expected urlset or sitemapindex)".to_string()),
+    }
+}
+
+fn parse_url_entry(node: &XmlNode, sitemap_url: &str) -> Option<SitemapUrl> {
+    let loc = resolve_href(&node.child_text("loc")?, sitemap_url)?;
+    Some(SitemapUrl {
+        loc,
+        lastmod: node.child_text("lastmod"),
+        changefreq: node.child_text("changefreq"),
+        priority: node
+            .child_text("priority")
+            .and_then(|priority| priority.parse::<f64>().ok()),
+    })
+}
+
+// 解析 robots.txt 中的 Sitemap: 行
+pub fn robots_sitemaps(robots: &str, robots_url: &str) -> Vec<String> {
+    let mut found = Vec::new();
+    for line in robots.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if let Some((key, value)) = line.split_once(':')
+            && key.trim().eq_ignore_ascii_case("sitemap")
+            && let Some(resolved) = resolve_href(value.trim(), robots_url)
+            && !found.contains(&resolved)
+        {
+            found.push(resolved);
+        }
+    }
+    found
+}
+
+// 传入的地址本身像站点地图时直接使用，否则依次尝试 robots.txt 与 /sitemap.xml
+pub async fn discover_sitemaps(
+    config: &FetchConfig,
+    options: &FetchOptions,
+    url: &str,
+) -> Result<Vec<String>, FetchError> {
+    let parsed = Url::parse(url).map_err(|e| FetchError::InvalidUrl(format!("{url}: {e}")))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(FetchError::InvalidUrl(format!(
+            "{url}: only http and https urls are supported"
+        )));
+    }
+
+    if looks_like_sitemap_url(&parsed) {
+        return Ok(vec![parsed.to_string()]);
+    }
+
+    let robots_url = parsed
+        .join("/robots.txt")
+        .map_err(|e| FetchError::InvalidUrl(format!("{url}: {e}")))?;
+    match fetcher::fetch_bytes(robots_url.as_str(), config, options).await {
+        Ok(resp) => {
+            let robots = content_type::decode_text(&resp.body, resp.content_type.as_deref());
+            let found = robots_sitemaps(&robots, robots_url.as_str());
+            if !found.is_empty() {
+                return Ok(found);
+            }
+        }
+        Err(e) => tracing::debug!("fetch {robots_url} failed: {e}"),
+    }
+
+    let fallback = parsed
+        .join("/sitemap.xml")
+        .map_err(|e| FetchError::InvalidUrl(format!("{url}: {e}")))?;
+    Ok(vec![fallback.to_string()])
+}
+
+fn looks_like_sitemap_url(url: &Url) -> bool {
+    let path = url.path().to_ascii_lowercase();
+    path.ends_with(".xml") || path.ends_with(".xml.gz")
+}
+
+pub async fn expand_sitemaps(
+    config: &FetchConfig,
+    options: &FetchOptions,
+    roots: Vec<String>,
+    filter: &SitemapFilter,
+) -> SitemapReport {
+    let mut report = SitemapReport::default();
+    let mut queue: VecDeque<(String, usize)> = roots.into_iter().map(|url| (url, 0)).collect();
+    let mut visited: HashSet<String> = HashSet::new();
+    let mut seen_locs: HashSet<String> = HashSet::new();
+
+    while let Some((sitemap_url, depth)) = queue.pop_front() {
+        if !visited.insert(sitemap_url.clone()) {
+            continue;
+        }
+        if visited.len() > MAX_SITEMAP_FILES {
+            report.truncated = true;
+            break;
+        }
+
+        let document = match fetch_sitemap(config, options, &sitemap_url).await {
+            Ok(document) => document,
+            Err(e) => {
+                report.errors.push(SitemapError {
+                    sitemap_url,
+                    error: e.to_string(),
+                    error_kind: e.kind(),
+                });
+                continue;
+            }
+        };
+        report.sitemaps.push(sitemap_url.clone());
+
+        match document {
+            SitemapDocument::Index(children) => {
+                if depth + 1 > MAX_SITEMAP_DEPTH {
+                    report.errors.push(SitemapError {
+                        sitemap_url,
+                        error: format!(
+                            "sitemap index nested deeper than {MAX_SITEMAP_DEPTH} levels"
+                        ),
+                        error_kind: FetchErrorKind::Parse,
+                    });
+                    continue;
+                }
+                queue.extend(children.into_iter().map(|child| (child, depth + 1)));
+            }
+            SitemapDocument::UrlSet(entries) => {
+                for entry in entries {
+                    if !filter.matches(&entry) || !seen_locs.insert(entry.loc.clone()) {
+                        continue;
+                    }
+                    if report.urls.len() >= filter.max_urls {
+                        report.truncated = true;
+                        return report;
+                    }
+                    report.urls.push(entry);
+                }
+            }
+        }
+    }
+
+    report
+}
+
+async fn fetch_sitemap(
+    config: &FetchConfig,
+    options: &FetchOptions,
+    sitemap_url: &str,
+) -> Result<SitemapDocument, FetchError> {
+    let resp = fetcher::fetch_bytes(sitemap_url, config, options).await?;
+    let text = decode_sitemap_body(resp, config.max_body_bytes)?;
+    parse_sitemap(&text, sitemap_url).map_err(FetchError::Parse)
+}
+
+// .xml.gz 站点地图按 gzip 魔数识别并解压，解压后的大小同样受响应体上限约束
+fn decode_sitemap_body(resp: FetchedResponse, limit: u64) -> Result<String, FetchError> {
+    if !resp.body.starts_with(b"\x1F\x8B") {
+        return Ok(content_type::decode_text(
+            &resp.body,
+            resp.content_type.as_deref(),
+        ));
+    }
+
+    let mut decoded = Vec::new();
+    GzDecoder::new(resp.body.as_slice())
+        .take(limit + 1)
+        .read_to_end(&mut decoded)
+        .map_err(|e| FetchError::Parse(format!("gunzip sitemap failed: {e}")))?;
+    if decoded.len() as u64 > limit {
+        return Err(FetchError::TooLarge { limit });
+    }
+    Ok(content_type::decode_text(&decoded, None))
+}
@@ -0,0 +1,336 @@
+use rmcp::schemars;
+use serde::Serialize;
+
+use crate::{
+    parser::{Attr, HtmlNode, attr},
+    text,
+};
+
+// HTML 规范中 colspan / rowspan 的上限
+const MAX_COLSPAN: usize = 1000;
+const MAX_ROWSPAN: usize = 65534;
+// 展开后的网格上限：超出列数的部分截断，单元格总数或文本总量超出时跳过整个表格，
+// 避免少量 colspan / rowspan 很大的单元格把网格撑到远超页面本身的大小
+const MAX_COLUMNS: usize = 1000;
+const MAX_CELLS: usize = 200_000;
+const MAX_GRID_BYTES: usize = 32 * 1024 * 1024;
+// 多行表头合并为单个列名时使用的分隔符
+const HEADER_SEPARATOR: &str = " / ";
+
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct Table {
+    // 在页面中的顺序，从 0 开始（嵌套表格按开始标签的先后计数）
+    pub position: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub csv: Option<String>,
+}
+
+// 表格中的一行及其来源：thead 中或全部由 th 组成的前导行视为表头
+struct RawRow<'a> {
+    in_head: bool,
+    cells: Vec<RawCell<'a>>,
+}
+
+struct RawCell<'a> {
+    header: bool,
+    colspan: usize,
+    rowspan: usize,
+    children: &'a [HtmlNode],
+}
+
+// 按文档顺序提取页面中所有非空表格；单元格文本保持原样，不做数值转换
+pub fn extract_tables(nodes: &[HtmlNode], with_csv: bool) -> Vec<Table> {
+    let mut found = Vec::new();
+    collect_table_nodes(nodes, &mut found);
+
+    found
+        .into_iter()
+        .filter_map(|(caption, rows)| build_table(caption, rows))
+        .enumerate()
+        .map(|(position, mut table)| {
+            table.position = position;
+            if with_csv {
+                table.csv = Some(to_csv(&table.headers, &table.rows));
+            }
+            table
+        })
+        .collect()
+}
+
+type RawTable<'a> = (Option<String>, Vec<RawRow<'a>>);
+
+fn collect_table_nodes<'a>(nodes: &'a [HtmlNode], found: &mut Vec<RawTable<'a>>) {
+    for node in nodes {
+        let HtmlNode::Element { tag, children, .. } = node else {
+            continue;
+        };
+        if tag == "table" {
+            let mut caption = None;
+            let mut rows = Vec::new();
+            collect_rows(children, false, &mut caption, &mut rows);
+            found.push((caption, rows));
+        }
+        // 继续向下查找，嵌套在单元格中的表格单独输出
+        collect_table_nodes(children, found);
+    }
+}
+
+fn collect_rows<'a>(
+    nodes: &'a [HtmlNode],
+    in_head: bool,
+    caption: &mut Option<String>,
+    rows: &mut Vec<RawRow<'a>>,
+) {
+    for node in nodes {
+        let HtmlNode::Element { tag, children, .. } = node else {
+            continue;
+        };
+        match tag.as_str() {
+            "caption" if caption.is_none() => {
+                let text = text::inline_text(children);
+                if !text.is_empty() {
+                    *caption = Some(text);
+                }
+            }
+            "thead" => collect_rows(children, true, caption, rows),
+            "tbody" | "tfoot" => collect_rows(children, false, caption, rows),
+            "tr" => rows.push(RawRow {
+                in_head,
+                cells: collect_cells(children),
+            }),
+            _ => {}
+        }
+    }
+}
+
+fn collect_cells(nodes: &[HtmlNode]) -> Vec<RawCell<'_>> {
+    nodes
+        .iter()
+        .filter_map(|node| match node {
+            HtmlNode::Element {
+                tag,
+                attrs,
+                children,
+            } if tag == "td" || tag == "th" => Some(RawCell {
+                header: tag == "th",
+                colspan: span_attr(attrs, "colspan").clamp(1, MAX_COLSPAN),
+                // rowspan="0" 表示延伸到表格末尾
+                rowspan: match span_attr(attrs, "rowspan") {
+                    0 => MAX_ROWSPAN,
+                    value => value.min(MAX_ROWSPAN),
+                },
+                children,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+fn span_attr(attrs: &[Attr], name: &str) -> usize {
+    attr(attrs, name)
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or(1)
+}
+
+// 展开 colspan / rowspan 得到规整的网格，再拆分出表头
+fn build_table(caption: Option<String>, raw_rows: Vec<RawRow<'_>>) -> Option<Table> {
+    let mut grid: Vec<Vec<Option<String>>> = Vec::new();
+    // 每列仍被上方单元格占据的剩余行数及其文本
+    let mut pending: Vec<Option<(usize, String)>> = Vec::new();
+    let mut header_rows = 0;
+    let mut in_header = true;
+    // 网格中已展开的文本量（每个单元格另计 1），超出上限时放弃该表格
+    let mut grid_bytes: usize = 0;
+    let mut grid_cells: usize = 0;
+
+    for raw in &raw_rows {
+        let mut row: Vec<Option<String>> = Vec::new();
+        let mut col = 0;
+        let mut span_bytes: usize = 0;
+        for cell in &raw.cells {
+            fill_pending(&mut pending, &mut row, &mut col);
+            if col >= MAX_COLUMNS {
+                break;
+            }
+            let colspan = cell.colspan.min(MAX_COLUMNS - col);
+            let text = cell_text(cell.children);
+            span_bytes = span_bytes.saturating_add((text.len() + 1).saturating_mul(colspan));
+            if grid_bytes.saturating_add(span_bytes) > MAX_GRID_BYTES {
+                return None;
+            }
+            for offset in 0..colspan {
+                let target = col + offset;
+                set_cell(&mut row, target, text.clone());
+                if cell.rowspan > 1 {
+                    if pending.len() <= target {
+                        pending.resize(target + 1, None);
+                    }
+                    pending[target] = Some((cell.rowspan - 1, text.clone()));
+                }
+            }
+            col += colspan;
+        }
+        // 行尾仍有被 rowspan 占据的列
+        fill_trailing_pending(&mut pending, &mut row, col);
+
+        if row.is_empty() {
+            continue;
+        }
+        grid_bytes += row
+            .iter()
+            .map(|cell| cell.as_ref().map_or(0, String::len) + 1)
+            .sum::<usize>();
+        grid_cells += row.len();
+        if grid_bytes > MAX_GRID_BYTES || grid_cells > MAX_CELLS {
+            return None;
+        }
+        let is_header = raw.in_head || raw.cells.iter().all(|cell| cell.header);
+        if in_header && is_header {
+            header_rows += 1;
+        } else {
+            in_header = false;
+        }
+        grid.push(row);
+    }
+
+    let width = grid.iter().map(Vec::len).max().unwrap_or(0);
+    if grid.is_empty() || grid.len().saturating_mul(width) > MAX_CELLS {
+        return None;
+    }
+
+    let mut rows: Vec<Vec<String>> = grid
+        .into_iter()
+        .map(|row| {
+            let mut row: Vec<String> = row.into_iter().map(Option::unwrap_or_default).collect();
+            row.resize(width, String::new());
+            row
+        })
+        .collect();
+
+    // 全部是表头行时当作没有表头，避免丢失数据
+    if header_rows == rows.len() {
+        header_rows = 0;
+    }
+    let body = rows.split_off(header_rows);
+    Some(Table {
+        position: 0,
+        caption,
+        headers: merge_headers(&rows, width),
+        rows: body,
+        csv: None,
+    })
+}
+
+// 从 col 开始填入被上方单元格 rowspan 占据的连续列
+fn fill_pending(
+    pending: &mut [Option<(usize, String)>],
+    row: &mut Vec<Option<String>>,
+    col: &mut usize,
+) {
+    while let Some(text) = take_pending(pending, *col) {
+        set_cell(row, *col, text);
+        *col += 1;
+    }
+}
+
+// 行尾：填入 col 之后所有仍被占据的列，中间未被占据的列留空
+fn fill_trailing_pending(
+    pending: &mut [Option<(usize, String)>],
+    row: &mut Vec<Option<String>>,
+    col: usize,
+) {
+    for index in col..pending.len() {
+        if let Some(text) = take_pending(pending, index) {
+            set_cell(row, index, text);
+        }
+    }
+}
+
+// 取出该列上方单元格的文本，并把剩余占据的行数减一
+fn take_pending(pending: &mut [Option<(usize, String)>], col: usize) -> Option<String> {
+    let slot = pending.get_mut(col)?;
+    let (remaining, text) = slot.as_mut()?;
+    let text = text.clone();
+    *remaining -= 1;
+    if *remaining == 0 {
+        *slot = None;
+    }
+    Some(text)
+}
+
+// 单元格文本不含嵌套的表格，嵌套表格会作为独立的表格输出
+fn cell_text(children: &[HtmlNode]) -> String {
+    fn without_tables(nodes: &[HtmlNode]) -> Vec<HtmlNode> {
+        nodes
+            .iter()
+            .filter_map(|node| match node {
+                HtmlNode::Element { tag, .. } if tag == "table" => None,
+                HtmlNode::Element {
+                    tag,
+                    attrs,
+                    children,
+                } => Some(HtmlNode::Element {
+                    tag: tag.clone(),
+                    attrs: attrs.clone(),
+                    children: without_tables(children),
+                }),
+                HtmlNode::Text(_) => Some(node.clone()),
+            })
+            .collect()
+    }
+
+    text::inline_text(&without_tables(children))
+}
+
+fn set_cell(row: &mut Vec<Option<String>>, col: usize, text: String) {
+    if row.len() <= col {
+        row.resize(col + 1, None);
+    }
+    row[col] = Some(text);
+}
+
+// 多行表头按列拼接，跳过空值和由 colspan / rowspan 产生的重复值
+fn merge_headers(header_rows: &[Vec<String>], width: usize) -> Vec<String> {
+    if header_rows.is_empty() {
+        return Vec::new();
+    }
+    (0..width)
+        .map(|col| {
+            let mut parts: Vec<&str> = Vec::new();
+            for row in header_rows {
+                let part = row[col].as_str();
+                if !part.is_empty() && parts.last() != Some(&part) {
+                    parts.push(part);
+                }
+            }
+            parts.join(HEADER_SEPARATOR)
+        })
+        .collect()
+}
+
+// 按 RFC 4180 输出，表头（如有）作为第一行
+fn to_csv(headers: &[String], rows: &[Vec<String>]) -> String {
+    let mut output = String::new();
+    let lines = (!headers.is_empty())
+        .then_some(headers)
+        .into_iter()
+        .chain(rows.iter().map(Vec::as_slice));
+    for line in lines {
+        let fields: Vec<String> = line.iter().map(|field| csv_field(field)).collect();
+        output.push_str(&fields.join(","));
+        output.push_str("\r\n");
+    }
+    output
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
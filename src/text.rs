@@ -1,10 +1,18 @@
-use crate::parser::HtmlNode;
+use crate::{
+    math::{self, MathNode},
+    parser::HtmlNode,
+};
 
 const BLOCK_TAGS: [&str; 20] = [
     "p", "div", "section", "article", "li", "h1", "h2", "h3", "h4", "h5", "h6",
     "blockquote", "tr", "table", "ul", "ol", "header", "footer", "nav", "main",
 ];
 const SKIP_TAGS: [&str; 4] = ["script", "style", "head", "noscript"];
+// 单行文本拼接时不额外补空格的行内元素
+const INLINE_TAGS: [&str; 20] = [
+    "a", "abbr", "b", "bdi", "bdo", "cite", "code", "data", "dfn", "em", "i", "kbd", "mark", "q",
+    "s", "small", "span", "strong", "sub", "sup",
+];
 
 pub fn convert_to_text(nodes: &[HtmlNode]) -> String {
     let mut output = String::new();
@@ -18,7 +26,27 @@ pub fn convert_to_text(nodes: &[HtmlNode]) -> String {
 fn append_node(node: &HtmlNode, output: &mut String) {
     match node {
         HtmlNode::Text(text) => push_text(output, text),
-        HtmlNode::Element { tag, children, .. } => {
+        HtmlNode::Element {
+            tag,
+            attrs,
+            children,
+        } => {
+            // 公式输出线性化的可读形式，独立公式单独成行
+            match math::classify(tag, attrs, children) {
+                Some(MathNode::Formula(formula)) => {
+                    let text = math::linearize(&formula.tex);
+                    if formula.display {
+                        output.push('\n');
+                        output.push_str(&text);
+                        output.push('\n');
+                    } else {
+                        push_text(output, &text);
+                    }
+                    return;
+                }
+                Some(MathNode::Rendered) => return,
+                None => {}
+            }
             if SKIP_TAGS.iter().any(|skip| skip == tag) {
                 return;
             }
@@ -87,6 +115,37 @@ fn normalize_whitespace(text: &str) -> String {
     output.trim().to_string()
 }
 
+// 拼接元素内的文本并把连续空白压缩为单个空格
+pub fn inline_text(nodes: &[HtmlNode]) -> String {
+    fn append(nodes: &[HtmlNode], output: &mut String) {
+        for node in nodes {
+            match node {
+                HtmlNode::Text(text) => {
+                    output.push_str(text);
+                }
+                HtmlNode::Element { tag, children, .. } => {
+                    if SKIP_TAGS.contains(&tag.as_str()) || tag == "template" {
+                        continue;
+                    }
+                    // 块级元素前后补空格，避免相邻段落或单元格的文字粘连
+                    let block = !INLINE_TAGS.contains(&tag.as_str());
+                    if block {
+                        output.push(' ');
+                    }
+                    append(children, output);
+                    if block {
+                        output.push(' ');
+                    }
+                }
+            }
+        }
+    }
+
+    let mut raw = String::new();
+    append(nodes, &mut raw);
+    raw.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
 pub fn strip_urls(text: &str) -> String {
     let chars: Vec<char> = text.chars().collect();
     let mut output = String::new();
@@ -102,6 +161,28 @@ pub fn strip_urls(text: &str) -> String {
     output
 }
 
+pub fn find_urls(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut urls = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if is_url_start(&chars, i) {
+            let end = skip_url(&chars, i);
+            let raw: String = chars[i..end].iter().collect();
+            let url = raw.trim_end_matches(['.', ',', ';', ':', '!', '?']);
+            if url.starts_with("www.") {
+                urls.push(format!("https://{url}"));
+            } else {
+                urls.push(url.to_string());
+            }
+            i = end;
+            continue;
+        }
+        i += 1;
+    }
+    urls
+}
+
 fn is_url_start(chars: &[char], index: usize) -> bool {
     if index > 0 {
         let prev = chars[index - 1];
@@ -0,0 +1,167 @@
+use std::collections::HashSet;
+
+use rmcp::schemars;
+use serde::Serialize;
+
+use crate::{
+    parser::{Attr, HtmlNode},
+    text,
+};
+
+// 预先分配的标题锚点，章节截取、目录与 Markdown 转换都读取它，保证三者一致
+pub const ANCHOR_ATTR: &str = "data-cleanfetch-anchor";
+
+// 转换过程中按文档顺序记录的标题
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heading {
+    pub level: u8,
+    pub text: String,
+    pub anchor: String,
+}
+
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+pub struct TocEntry {
+    // 标题级别，1–6 对应 h1–h6
+    pub level: u8,
+    pub text: String,
+    // 页面中的 id；标题没有 id 时按 GitHub 规则由文字生成
+    pub anchor: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TocEntry>,
+}
+
+// 页面内锚点的分配：优先使用 HTML 中的 id，其余按文字生成且不与已有锚点重复
+#[derive(Debug, Clone, Default)]
+pub struct AnchorSet {
+    used: HashSet<String>,
+}
+
+impl AnchorSet {
+    pub fn assign(&mut self, attrs: &[Attr], children: &[HtmlNode], text: &str) -> String {
+        if let Some(id) = heading_id(attrs, children)
+            && self.used.insert(id.to_string())
+        {
+            return id.to_string();
+        }
+        let base = slugify(text);
+        let mut anchor = base.clone();
+        let mut suffix = 1;
+        while !self.used.insert(anchor.clone()) {
+            anchor = format!("{base}-{suffix}");
+            suffix += 1;
+        }
+        anchor
+    }
+}
+
+// 按文档顺序为所有非空标题分配锚点并写入 ANCHOR_ATTR；已分配过的标题保持不变
+pub fn assign_anchors(nodes: &mut [HtmlNode]) {
+    fn walk(nodes: &mut [HtmlNode], anchors: &mut AnchorSet) {
+        for node in nodes {
+            let HtmlNode::Element {
+                tag,
+                attrs,
+                children,
+            } = node
+            else {
+                continue;
+            };
+            if !matches!(tag.as_str(), "h1" | "h2" | "h3" | "h4" | "h5" | "h6") {
+                walk(children, anchors);
+                continue;
+            }
+            if let Some(anchor) = assigned_anchor(attrs) {
+                anchors.used.insert(anchor.to_string());
+                continue;
+            }
+            let title = text::inline_text(children);
+            if title.is_empty() {
+                continue;
+            }
+            let anchor = anchors.assign(attrs, children, &title);
+            attrs.push(Attr {
+                name: ANCHOR_ATTR.to_string(),
+                value: anchor,
+            });
+        }
+    }
+
+    walk(nodes, &mut AnchorSet::default());
+}
+
+// assign_anchors 写入的锚点
+pub fn assigned_anchor(attrs: &[Attr]) -> Option<&str> {
+    attrs
+        .iter()
+        .find(|attr| attr.name == ANCHOR_ATTR)
+        .map(|attr| attr.value.as_str())
+}
+
+// 把平铺的标题列表按级别嵌套；级别跳跃（如 h2 后直接 h4）时仍作为子项
+pub fn build_toc(headings: &[Heading]) -> Vec<TocEntry> {
+    nest_headings(headings, &mut 0, None)
+}
+
+// 从 next 开始收集级别高于 parent_level 的标题，遇到同级或更高级标题时返回上一层
+fn nest_headings(
+    headings: &[Heading],
+    next: &mut usize,
+    parent_level: Option<u8>,
+) -> Vec<TocEntry> {
+    let mut entries = Vec::new();
+    while let Some(heading) = headings.get(*next) {
+        if parent_level.is_some_and(|level| heading.level <= level) {
+            break;
+        }
+        *next += 1;
+        let children = nest_headings(headings, next, Some(heading.level));
+        entries.push(TocEntry {
+            level: heading.level,
+            text: heading.text.clone(),
+            anchor: heading.anchor.clone(),
+            children,
+        });
+    }
+    entries
+}
+
+// 标题自身的 id，或其内部第一个带 id / name 的元素（如 MediaWiki 的 span.mw-headline、<a name>）
+fn heading_id<'a>(attrs: &'a [Attr], children: &'a [HtmlNode]) -> Option<&'a str> {
+    fn find<'a>(attrs: &'a [Attr], names: &[&str]) -> Option<&'a str> {
+        attrs
+            .iter()
+            .find(|attr| names.contains(&attr.name.as_str()) && !attr.value.trim().is_empty())
+            .map(|attr| attr.value.trim())
+    }
+
+    find(attrs, &["id"]).or_else(|| {
+        children.iter().find_map(|child| match child {
+            HtmlNode::Element {
+                tag,
+                attrs,
+                children,
+            } => {
+                let names: &[&str] = if tag == "a" { &["id", "name"] } else { &["id"] };
+                find(attrs, names).or_else(|| heading_id(&[], children))
+            }
+            HtmlNode::Text(_) => None,
+        })
+    })
+}
+
+// GitHub 风格：转小写，去掉标点，空白替换为 -
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for ch in text.trim().chars() {
+        if ch.is_alphanumeric() || ch == '_' || ch == '-' {
+            slug.extend(ch.to_lowercase());
+        } else if ch.is_whitespace() {
+            slug.push('-');
+        }
+    }
+    if slug.is_empty() {
+        "section".to_string()
+    } else {
+        slug
+    }
+}
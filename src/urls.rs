@@ -1,6 +1,6 @@
 use std::collections::HashSet;
 
-use crate::parser::{Attr, HtmlNode};
+use crate::parser::{HtmlNode, attr};
 
 const LINK_REL_ALLOWED: [&str; 5] = ["canonical", "alternate", "prev", "next", "amphtml"];
 
@@ -15,6 +15,47 @@ pub fn extract_urls(nodes: &[HtmlNode], base_url: &str) -> String {
     output.join("\n")
 }
 
+// 爬取时跟随的链接：只取 <a> / <area> 的 href，不含表单提交地址、iframe 与 <link> 等资源，返回去重后的绝对地址
+pub fn extract_links(nodes: &[HtmlNode], base_url: &str) -> Vec<String> {
+    fn collect(
+        nodes: &[HtmlNode],
+        base: &Option<BaseUrl>,
+        seen: &mut HashSet<String>,
+        output: &mut Vec<String>,
+    ) {
+        for node in nodes {
+            let HtmlNode::Element {
+                tag,
+                attrs,
+                children,
+            } = node
+            else {
+                continue;
+            };
+            if (tag == "a" || tag == "area")
+                && let Some(url) = attr(attrs, "href").and_then(|href| resolve_url(href, base))
+                && seen.insert(url.clone())
+            {
+                output.push(url);
+            }
+            collect(children, base, seen, output);
+        }
+    }
+
+    let mut output = Vec::new();
+    collect(
+        nodes,
+        &parse_base_url(base_url),
+        &mut HashSet::new(),
+        &mut output,
+    );
+    output
+}
+
+pub fn resolve_href(href_raw: &str, base_url: &str) -> Option<String> {
+    resolve_url(href_raw, &parse_base_url(base_url))
+}
+
 fn collect_urls(
     node: &HtmlNode,
     base: &Option<BaseUrl>,
@@ -30,7 +71,7 @@ fn collect_urls(
         } => {
             match tag.as_str() {
                 "a" => {
-                    if let Some(href_raw) = get_attr_value(attrs, "href") {
+                    if let Some(href_raw) = attr(attrs, "href") {
                         add_url(
                             build_anchor_desc(children, href_raw),
                             href_raw,
@@ -41,24 +82,24 @@ fn collect_urls(
                     }
                 }
                 "area" => {
-                    if let Some(href_raw) = get_attr_value(attrs, "href") {
+                    if let Some(href_raw) = attr(attrs, "href") {
                         add_url("area href".to_string(), href_raw, base, seen, output);
                     }
                 }
                 "form" => {
-                    if let Some(action_raw) = get_attr_value(attrs, "action") {
+                    if let Some(action_raw) = attr(attrs, "action") {
                         add_url("form action".to_string(), action_raw, base, seen, output);
                     }
                 }
                 "iframe" => {
-                    if let Some(src_raw) = get_attr_value(attrs, "src") {
+                    if let Some(src_raw) = attr(attrs, "src") {
                         add_url("iframe src".to_string(), src_raw, base, seen, output);
                     }
                 }
                 "link" => {
-                    if let Some(rel_raw) = get_attr_value(attrs, "rel")
+                    if let Some(rel_raw) = attr(attrs, "rel")
                         && rel_matches_allowed(rel_raw)
-                        && let Some(href_raw) = get_attr_value(attrs, "href")
+                        && let Some(href_raw) = attr(attrs, "href")
                     {
                         add_url(
                             format!("link rel={}", rel_raw.trim()),
@@ -120,13 +161,6 @@ fn extract_text(node: &HtmlNode) -> String {
     }
 }
 
-fn get_attr_value<'a>(attrs: &'a [Attr], name: &str) -> Option<&'a str> {
-    attrs
-        .iter()
-        .find(|attr| attr.name == name)
-        .map(|attr| attr.value.as_str())
-}
-
 fn rel_matches_allowed(rel: &str) -> bool {
     let lower = rel.to_lowercase();
     lower
@@ -174,6 +208,17 @@ fn resolve_url(href_raw: &str, base: &Option<BaseUrl>) -> Option<String> {
         ));
     }
 
+    // 仅含查询串的相对地址（如 ?page=2）保留当前页路径
+    if href.starts_with('?') {
+        return Some(format!(
+            "{}://{}{}{}",
+            base.scheme,
+            base.host_with_port(),
+            base.path,
+            href
+        ));
+    }
+
     let (path_part, suffix) = split_suffix(href);
     let combined = format!("{}{}", base.base_dir, path_part);
     let normalized = normalize_path(&combined);
@@ -236,6 +281,7 @@ struct BaseUrl {
     scheme: String,
     host: String,
     port: Option<String>,
+    path: String,
     base_dir: String,
 }
 
@@ -305,6 +351,7 @@ fn parse_base_url(input: &str) -> Option<BaseUrl> {
         scheme: scheme.to_string(),
         host: host.to_string(),
         port,
+        path: path.to_string(),
         base_dir,
     })
 }
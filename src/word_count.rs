@@ -22,7 +22,7 @@ pub fn count_words(input: &str) -> u32 {
     count
 }
 
-fn is_cjk_like(ch: char) -> bool {
+pub fn is_cjk_like(ch: char) -> bool {
     is_cjk(ch) || is_hiragana(ch) || is_katakana(ch) || is_hangul(ch)
 }
 
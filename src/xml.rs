@@ -0,0 +1,235 @@
+use quick_xml::{Reader, events::Event};
+
+use crate::{entities::decode_entities, parser::Attr};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum XmlNode {
+    Element {
+        name: String,
+        attrs: Vec<Attr>,
+        children: Vec<XmlNode>,
+    },
+    Text(String),
+}
+
+impl XmlNode {
+    // 去掉命名空间前缀后的小写标签名，便于匹配 rss / atom / sitemap 等格式
+    pub fn local_name(&self) -> Option<String> {
+        match self {
+            XmlNode::Element { name, .. } => {
+                Some(name.rsplit(':').next().unwrap_or(name).to_ascii_lowercase())
+            }
+            XmlNode::Text(_) => None,
+        }
+    }
+
+    pub fn attr(&self, key: &str) -> Option<&str> {
+        match self {
+            XmlNode::Element { attrs, .. } => attrs
+                .iter()
+                .find(|attr| attr.name.eq_ignore_ascii_case(key))
+                .map(|attr| attr.value.as_str()),
+            XmlNode::Text(_) => None,
+        }
+    }
+
+    pub fn children(&self) -> &[XmlNode] {
+        match self {
+            XmlNode::Element { children, .. } => children,
+            XmlNode::Text(_) => &[],
+        }
+    }
+
+    pub fn child_elements<'a>(&'a self, local: &'a str) -> impl Iterator<Item = &'a XmlNode> {
+        self.children()
+            .iter()
+            .filter(move |child| child.local_name().as_deref() == Some(local))
+    }
+
+    pub fn child_element<'a>(&'a self, local: &'a str) -> Option<&'a XmlNode> {
+        self.child_elements(local).next()
+    }
+
+    pub fn text(&self) -> String {
+        match self {
+            XmlNode::Text(text) => text.clone(),
+            XmlNode::Element { children, .. } => children
+                .iter()
+                .map(XmlNode::text)
+                .collect::<Vec<_>>()
+                .join(""),
+        }
+    }
+
+    pub fn child_text(&self, local: &str) -> Option<String> {
+        self.child_element(local)
+            .map(|child| child.text().trim().to_string())
+            .filter(|text| !text.is_empty())
+    }
+}
+
+struct OpenElement {
+    name: String,
+    attrs: Vec<Attr>,
+    children: Vec<XmlNode>,
+}
+
+pub fn parse_xml(input: &str) -> Result<Vec<XmlNode>, String> {
+    let mut reader = Reader::from_str(input);
+    let mut stack: Vec<OpenElement> = Vec::new();
+    let mut root: Vec<XmlNode> = Vec::new();
+    let mut text = String::new();
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("parse xml failed at {}: {e}", reader.buffer_position()))?;
+
+        match event {
+            Event::Start(start) => {
+                flush_text(&mut text, &mut stack, &mut root);
+                stack.push(OpenElement {
+                    name: start.name().as_ref().to_string(),
+                    attrs: collect_attrs(&start),
+                    children: Vec::new(),
+                });
+            }
+            Event::Empty(start) => {
+                flush_text(&mut text, &mut stack, &mut root);
+                let node = XmlNode::Element {
+                    name: start.name().as_ref().to_string(),
+                    attrs: collect_attrs(&start),
+                    children: Vec::new(),
+                };
+                push_node(node, &mut stack, &mut root);
+            }
+            Event::End(_) => {
+                flush_text(&mut text, &mut stack, &mut root);
+                if let Some(elem) = stack.pop() {
+                    let node = XmlNode::Element {
+                        name: elem.name,
+                        attrs: elem.attrs,
+                        children: elem.children,
+                    };
+                    push_node(node, &mut stack, &mut root);
+                }
+            }
+            Event::Text(content) => text.push_str(&content.into_inner()),
+            Event::CData(content) => text.push_str(&content.into_inner()),
+            Event::GeneralRef(reference) => {
+                if let Ok(Some(ch)) = reference.resolve_char_ref() {
+                    text.push(ch);
+                } else {
+                    text.push_str(&decode_entities(&format!("&{};", reference.into_inner())));
+                }
+            }
+            Event::Eof => break,
+            Event::Comment(_) | Event::Decl(_) | Event::PI(_) | Event::DocType(_) => {}
+        }
+    }
+
+    flush_text(&mut text, &mut stack, &mut root);
+    while let Some(elem) = stack.pop() {
+        let node = XmlNode::Element {
+            name: elem.name,
+            attrs: elem.attrs,
+            children: elem.children,
+        };
+        push_node(node, &mut stack, &mut root);
+    }
+
+    Ok(root)
+}
+
+pub fn root_element(nodes: &[XmlNode]) -> Option<&XmlNode> {
+    nodes
+        .iter()
+        .find(|node| matches!(node, XmlNode::Element { .. }))
+}
+
+pub fn pretty_print(nodes: &[XmlNode]) -> String {
+    let mut output = String::new();
+    for node in nodes {
+        write_node(node, 0, &mut output);
+    }
+    output.trim_end().to_string()
+}
+
+fn collect_attrs(start: &quick_xml::events::BytesStart<'_>) -> Vec<Attr> {
+    start
+        .attributes()
+        .flatten()
+        .map(|attr| Attr {
+            name: attr.key.as_ref().to_string(),
+            value: decode_entities(&attr.value),
+        })
+        .collect()
+}
+
+fn flush_text(text: &mut String, stack: &mut [OpenElement], root: &mut Vec<XmlNode>) {
+    if !text.trim().is_empty() {
+        let node = XmlNode::Text(text.trim().to_string());
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => root.push(node),
+        }
+    }
+    text.clear();
+}
+
+fn push_node(node: XmlNode, stack: &mut [OpenElement], root: &mut Vec<XmlNode>) {
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(node),
+        None => root.push(node),
+    }
+}
+
+fn write_node(node: &XmlNode, depth: usize, output: &mut String) {
+    let indent = "  ".repeat(depth);
+    match node {
+        XmlNode::Text(text) => {
+            output.push_str(&indent);
+            output.push_str(&escape_text(text));
+            output.push('\n');
+        }
+        XmlNode::Element {
+            name,
+            attrs,
+            children,
+        } => {
+            output.push_str(&indent);
+            output.push('<');
+            output.push_str(name);
+            for attr in attrs {
+                output.push_str(&format!(" {}=\"{}\"", attr.name, escape_attr(&attr.value)));
+            }
+
+            match children.as_slice() {
+                [] => output.push_str("/>\n"),
+                [XmlNode::Text(text)] if !text.contains('\n') => {
+                    output.push('>');
+                    output.push_str(&escape_text(text));
+                    output.push_str(&format!("</{name}>\n"));
+                }
+                _ => {
+                    output.push_str(">\n");
+                    for child in children {
+                        write_node(child, depth + 1, output);
+                    }
+                    output.push_str(&indent);
+                    output.push_str(&format!("</{name}>\n"));
+                }
+            }
+        }
+    }
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn escape_attr(value: &str) -> String {
+    escape_text(value).replace('"', "&quot;")
+}
@@ -0,0 +1,41 @@
+use std::{fs, path::Path};
+
+// 逐个比较 tests/golden/<name> 下的 *.html 经 render 转换后的输出与同名期望文件，
+// render 返回 (扩展名, 输出) 列表；设置 UPDATE_GOLDEN=1 时改为重写期望文件
+pub fn check_golden_dir(name: &str, render: impl Fn(&str) -> Vec<(&'static str, String)>) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(name);
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+
+    let mut cases: Vec<_> = fs::read_dir(&dir)
+        .expect("golden directory should exist")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "html"))
+        .collect();
+    cases.sort();
+    assert!(!cases.is_empty(), "no golden cases in {}", dir.display());
+
+    let mut mismatches = Vec::new();
+    for html_path in cases {
+        let html = fs::read_to_string(&html_path).expect("read html");
+        for (extension, output) in render(&html) {
+            let actual = format!("{output}\n");
+            let expected_path = html_path.with_extension(extension);
+
+            if update {
+                fs::write(&expected_path, &actual).expect("write golden file");
+                continue;
+            }
+            let expected = fs::read_to_string(&expected_path).unwrap_or_default();
+            if actual != expected {
+                mismatches.push(format!(
+                    "{}\n--- expected ---\n{expected}--- actual ---\n{actual}",
+                    expected_path.display()
+                ));
+            }
+        }
+    }
+
+    assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+}
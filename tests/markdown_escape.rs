@@ -0,0 +1,200 @@
+use cleanfetchrmcp::{
+    converter::{EscapeMode, MarkdownOptions, convert_to_markdown_with},
+    parser::parse_html,
+};
+use pulldown_cmark::{Event, Options, Parser, Tag};
+
+// 正文中容易被误解析为 Markdown 标记的字面量文本
+const LITERALS: &[&str] = &[
+    "2 * 3 = 6",
+    "# of users",
+    "#hashtag and ## twice",
+    "1. not a list",
+    "2) not a list either",
+    "- dash at start",
+    "+ plus at start",
+    "* star at start",
+    "> not a quote",
+    ">not a quote either",
+    "a*b*c",
+    "**not bold**",
+    "_not emphasis_",
+    "snake_case_name",
+    "use `code` here",
+    "[link](http://example.com)",
+    "see [1] and [2]",
+    "![image](x.png)",
+    "<div> is a tag",
+    "&amp; is an entity",
+    "C:\\path\\*",
+    "~~not struck~~",
+    "***",
+    "---",
+    "===",
+    "[^1] footnote-like",
+];
+
+fn options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_FOOTNOTES
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn to_markdown(html: &str, escape: EscapeMode) -> String {
+    convert_to_markdown_with(
+        &parse_html(html),
+        &MarkdownOptions {
+            escape,
+            ..Default::default()
+        },
+    )
+}
+
+// 解析 Markdown，返回纯文本内容以及是否出现了文本以外的行内或块级结构
+fn parse_markdown(markdown: &str) -> (String, Vec<String>) {
+    let mut text = String::new();
+    let mut structures = Vec::new();
+    for event in Parser::new_ext(markdown, options()) {
+        match event {
+            Event::Text(value) => text.push_str(&value),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            Event::Start(Tag::Paragraph)
+            | Event::Start(Tag::Table(_))
+            | Event::Start(Tag::TableHead)
+            | Event::Start(Tag::TableRow)
+            | Event::Start(Tag::TableCell)
+            | Event::End(_) => {}
+            Event::Start(tag) => structures.push(format!("{tag:?}")),
+            other => structures.push(format!("{other:?}")),
+        }
+    }
+    (text, structures)
+}
+
+#[test]
+fn literal_text_round_trips_in_both_modes() {
+    for mode in [EscapeMode::Minimal, EscapeMode::Strict] {
+        for literal in LITERALS {
+            let markdown = to_markdown(&format!("<p>{}</p>", escape_html(literal)), mode);
+            let (text, structures) = parse_markdown(&markdown);
+            assert_eq!(text, *literal, "{mode:?}: {markdown:?}");
+            assert!(
+                structures.is_empty(),
+                "{mode:?}: {markdown:?} parsed as {structures:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn literal_text_after_line_break_round_trips() {
+    for literal in LITERALS {
+        let html = format!("<p>first line<br>{}</p>", escape_html(literal));
+        let markdown = to_markdown(&html, EscapeMode::Minimal);
+        let (text, structures) = parse_markdown(&markdown);
+        assert_eq!(text, format!("first line {literal}"), "{markdown:?}");
+        assert!(
+            structures.is_empty(),
+            "{markdown:?} parsed as {structures:?}"
+        );
+    }
+}
+
+#[test]
+fn mid_line_block_markers_are_not_escaped() {
+    let cases = [
+        ("<p><a href=\"/x\">x</a> - y</p>", "x - y", "Link"),
+        ("<p><b>a</b> 1. b</p>", "a 1. b", "Strong"),
+        ("<p><em>a</em> > b</p>", "a > b", "Emphasis"),
+        ("<p><strong>a</strong> # b</p>", "a # b", "Strong"),
+    ];
+    for (html, expected, structure) in cases {
+        let markdown = to_markdown(html, EscapeMode::Minimal);
+        assert!(!markdown.contains('\\'), "{html}: {markdown:?}");
+        let (text, structures) = parse_markdown(&markdown);
+        assert_eq!(text, expected, "{html}: {markdown:?}");
+        assert!(
+            structures.iter().all(|found| found.starts_with(structure)),
+            "{html}: {markdown:?} parsed as {structures:?}"
+        );
+    }
+}
+
+#[test]
+fn markup_is_preserved_next_to_escaped_text() {
+    let markdown = to_markdown(
+        "<p><strong>bold</strong> * not emphasis * and <em>real</em></p>",
+        EscapeMode::Minimal,
+    );
+    let (text, structures) = parse_markdown(&markdown);
+    assert_eq!(text, "bold * not emphasis * and real");
+    assert_eq!(structures, vec!["Strong", "Emphasis"]);
+}
+
+#[test]
+fn table_cells_round_trip() {
+    for mode in [EscapeMode::Minimal, EscapeMode::Strict] {
+        let markdown = to_markdown(
+            "<table><tr><th>name</th><th>expr</th></tr>\
+             <tr><td>a | b</td><td>*x* [y]</td></tr></table>",
+            mode,
+        );
+        let (text, structures) = parse_markdown(&markdown);
+        assert_eq!(text, "nameexpra | b*x* [y]", "{mode:?}: {markdown:?}");
+        assert!(structures.is_empty(), "{mode:?}: {markdown:?}");
+    }
+}
+
+#[test]
+fn minimal_mode_leaves_harmless_characters_alone() {
+    let markdown = to_markdown(
+        "<p>2 * 3 uses snake_case and costs $5 (approx.)</p>",
+        EscapeMode::Minimal,
+    );
+    assert_eq!(markdown, "2 * 3 uses snake_case and costs $5 (approx.)");
+}
+
+#[test]
+fn strict_mode_escapes_every_special_character() {
+    let markdown = to_markdown("<p>2 * 3 uses snake_case</p>", EscapeMode::Strict);
+    assert_eq!(markdown, "2 \\* 3 uses snake\\_case");
+}
+
+#[test]
+fn inline_code_in_table_cells_keeps_row_structure() {
+    for mode in [EscapeMode::Minimal, EscapeMode::Strict] {
+        let markdown = to_markdown(
+            "<table><tr><th>op</th><th>key</th></tr>\
+             <tr><td><code>a | b</code></td><td><kbd>Ctrl\n+ C</kbd></td></tr></table>",
+            mode,
+        );
+        let mut cells_per_row = Vec::new();
+        let mut code = Vec::new();
+        for event in Parser::new_ext(&markdown, options()) {
+            match event {
+                Event::Start(Tag::TableHead) | Event::Start(Tag::TableRow) => cells_per_row.push(0),
+                Event::Start(Tag::TableCell) => {
+                    *cells_per_row.last_mut().expect("cell inside a row") += 1
+                }
+                Event::Code(value) => code.push(value.to_string()),
+                _ => {}
+            }
+        }
+        assert_eq!(cells_per_row, vec![2, 2], "{mode:?}: {markdown:?}");
+        assert_eq!(code, vec!["a | b", "Ctrl + C"], "{mode:?}: {markdown:?}");
+    }
+}
+
+#[test]
+fn paired_dollar_signs_do_not_become_inline_math() {
+    for mode in [EscapeMode::Minimal, EscapeMode::Strict] {
+        let markdown = to_markdown("<p>write $x$ literally, costs $5 or $10</p>", mode);
+        let math = Parser::new_ext(&markdown, options() | Options::ENABLE_MATH)
+            .any(|event| matches!(event, Event::InlineMath(_) | Event::DisplayMath(_)));
+        assert!(!math, "{mode:?}: {markdown:?}");
+    }
+}
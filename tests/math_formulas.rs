@@ -0,0 +1,13 @@
+mod common;
+
+// tests/golden/math 下 *.html 转换后的 Markdown 与同名 *.md、纯文本与同名 *.txt 比较：
+// Markdown 覆盖公式识别与 MathML 转 TeX，纯文本覆盖线性化
+#[test]
+fn math_formulas_match_golden_files() {
+    common::check_golden_dir("math", |html| {
+        vec![
+            ("md", cleanfetchrmcp::html_to_markdown(html)),
+            ("txt", cleanfetchrmcp::html_to_text(html)),
+        ]
+    });
+}
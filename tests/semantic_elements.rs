@@ -0,0 +1,9 @@
+mod common;
+
+// tests/golden/semantic 下 *.html 转换后的 Markdown 与同名 *.md 比较
+#[test]
+fn semantic_elements_match_golden_files() {
+    common::check_golden_dir("semantic", |html| {
+        vec![("md", cleanfetchrmcp::html_to_markdown(html))]
+    });
+}